@@ -0,0 +1,93 @@
+//! Authenticator using a store of hashed bearer tokens.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+
+use super::{AuthenticationError, Authenticator, RecipientId};
+
+/// Authenticator that resolves bearer tokens from a store of token hashes.
+///
+/// Only the hex encoded SHA-256 digest of every token is stored, so the
+/// bearer tokens themselves never have to be persisted by the server.
+#[derive(Debug, Clone, Default)]
+pub struct HashedTokenAuthenticator {
+    token_hashes: HashMap<String, RecipientId>,
+}
+
+impl HashedTokenAuthenticator {
+    /// Create a new authenticator without any tokens.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compute the hex encoded SHA-256 digest of a bearer token.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use delta_sharing_server::auth::hashed_token::HashedTokenAuthenticator;
+    ///
+    /// assert_eq!(
+    ///     HashedTokenAuthenticator::hash_token("foo_token"),
+    ///     "1a432020eb63556a6ba4b9a11ee325c98148c4ba342c08bee69bf0580b818025"
+    /// );
+    /// ```
+    pub fn hash_token(token: &str) -> String {
+        hex::encode(Sha256::digest(token.as_bytes()))
+    }
+
+    /// Add the hex encoded SHA-256 digest of a bearer token issued to the
+    /// named recipient.
+    pub fn add_token_hash(&mut self, token_hash: impl AsRef<str>, recipient: impl Into<String>) {
+        self.token_hashes.insert(
+            token_hash.as_ref().to_ascii_lowercase(),
+            RecipientId::known(recipient),
+        );
+    }
+
+    /// Add a bearer token issued to the named recipient. Only the digest of
+    /// the token is retained.
+    pub fn add_token(&mut self, token: &str, recipient: impl Into<String>) {
+        self.add_token_hash(Self::hash_token(token), recipient);
+    }
+}
+
+#[async_trait]
+impl Authenticator for HashedTokenAuthenticator {
+    async fn authenticate(&self, token: &str) -> Result<RecipientId, AuthenticationError> {
+        self.token_hashes
+            .get(&Self::hash_token(token))
+            .cloned()
+            .ok_or(AuthenticationError::InvalidToken)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn authenticate() {
+        let mut authenticator = HashedTokenAuthenticator::new();
+        authenticator.add_token_hash(
+            "1A432020EB63556A6BA4B9A11EE325C98148C4BA342C08BEE69BF0580B818025",
+            "foo",
+        );
+        authenticator.add_token("bar_token", "bar");
+
+        assert_eq!(
+            authenticator.authenticate("foo_token").await.unwrap(),
+            RecipientId::known("foo")
+        );
+        assert_eq!(
+            authenticator.authenticate("bar_token").await.unwrap(),
+            RecipientId::known("bar")
+        );
+        assert_eq!(
+            authenticator.authenticate("baz_token").await.unwrap_err(),
+            AuthenticationError::InvalidToken
+        );
+    }
+}
@@ -0,0 +1,98 @@
+//! Traits and types for authenticating recipients.
+
+use std::{error::Error, fmt::Display};
+
+use async_trait::async_trait;
+
+pub mod hashed_token;
+pub mod static_token;
+
+/// Trait implemented by authenticators that resolve a bearer token to the
+/// identity of the recipient it was issued to.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait Authenticator: Send + Sync {
+    /// Resolve the bearer token presented by a client to a recipient.
+    async fn authenticate(&self, token: &str) -> Result<RecipientId, AuthenticationError>;
+}
+
+/// Identity of the recipient that issued a request.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RecipientId {
+    /// The request was not authenticated because the server has no
+    /// authenticator configured.
+    Anonymous,
+    /// The request was authenticated as the named recipient.
+    Known(String),
+}
+
+impl RecipientId {
+    /// Create a new identity for the named recipient.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use delta_sharing_server::auth::RecipientId;
+    ///
+    /// let recipient = RecipientId::known("acme");
+    /// assert_eq!(recipient.name(), Some("acme"));
+    /// ```
+    pub fn known(name: impl Into<String>) -> Self {
+        Self::Known(name.into())
+    }
+
+    /// Retrieve the name of the recipient, if the recipient is known.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use delta_sharing_server::auth::RecipientId;
+    ///
+    /// assert_eq!(RecipientId::Anonymous.name(), None);
+    /// ```
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Self::Anonymous => None,
+            Self::Known(name) => Some(name),
+        }
+    }
+}
+
+impl Display for RecipientId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Anonymous => write!(f, "anonymous"),
+            Self::Known(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Errors that can occur during the authentication of a recipient.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthenticationError {
+    /// The request did not contain a bearer token.
+    MissingToken,
+    /// The bearer token does not belong to any recipient.
+    InvalidToken,
+    /// Other error
+    Other {
+        /// Reason why this error occurred.
+        reason: String,
+    },
+}
+
+impl Display for AuthenticationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthenticationError::MissingToken => {
+                write!(f, "the request does not contain a bearer token")
+            }
+            AuthenticationError::InvalidToken => write!(f, "the bearer token is invalid"),
+            AuthenticationError::Other { .. } => {
+                write!(f, "the recipient could not be authenticated")
+            }
+        }
+    }
+}
+
+impl Error for AuthenticationError {}
@@ -0,0 +1,66 @@
+//! Authenticator using a fixed list of bearer tokens.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use super::{AuthenticationError, Authenticator, RecipientId};
+
+/// Authenticator that resolves bearer tokens from an in-memory list.
+///
+/// The tokens are kept in plain text, which makes this authenticator most
+/// suitable for development and testing. Use the
+/// [`HashedTokenAuthenticator`](super::hashed_token::HashedTokenAuthenticator)
+/// to avoid keeping the tokens themselves in memory.
+#[derive(Debug, Clone, Default)]
+pub struct StaticTokenAuthenticator {
+    tokens: HashMap<String, RecipientId>,
+}
+
+impl StaticTokenAuthenticator {
+    /// Create a new authenticator without any tokens.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a bearer token issued to the named recipient.
+    pub fn add_token(&mut self, token: impl Into<String>, recipient: impl Into<String>) {
+        self.tokens
+            .insert(token.into(), RecipientId::known(recipient));
+    }
+}
+
+#[async_trait]
+impl Authenticator for StaticTokenAuthenticator {
+    async fn authenticate(&self, token: &str) -> Result<RecipientId, AuthenticationError> {
+        self.tokens
+            .get(token)
+            .cloned()
+            .ok_or(AuthenticationError::InvalidToken)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn authenticate() {
+        let mut authenticator = StaticTokenAuthenticator::new();
+        authenticator.add_token("foo_token", "foo");
+        authenticator.add_token("bar_token", "bar");
+
+        assert_eq!(
+            authenticator.authenticate("foo_token").await.unwrap(),
+            RecipientId::known("foo")
+        );
+        assert_eq!(
+            authenticator.authenticate("bar_token").await.unwrap(),
+            RecipientId::known("bar")
+        );
+        assert_eq!(
+            authenticator.authenticate("baz_token").await.unwrap_err(),
+            AuthenticationError::InvalidToken
+        );
+    }
+}
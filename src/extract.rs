@@ -1,15 +1,70 @@
-use std::ops::Deref;
+use std::{ops::Deref, sync::Arc};
 
 use async_trait::async_trait;
-use axum::{extract::FromRequestParts, http::request::Parts, Json};
+use axum::{
+    extract::FromRequestParts,
+    http::{header::AUTHORIZATION, request::Parts},
+    Json,
+};
 use chrono::{DateTime, Utc};
 use serde::Deserialize;
 
 use crate::{
+    auth::{AuthenticationError, RecipientId},
     error::ServerError,
-    protocol::{share::ListCursor, table::Version},
+    protocol::{
+        capabilities::{Capabilities, CAPABILITIES_HEADER},
+        predicate::Predicate,
+        share::ListCursor,
+        table::{TableChangesQuery, TableDataQuery, TableDataVersion, Version, VersionRange},
+    },
+    state::SharingServerState,
 };
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recipient(pub RecipientId);
+
+#[async_trait]
+impl FromRequestParts<Arc<SharingServerState>> for Recipient {
+    type Rejection = ServerError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<SharingServerState>,
+    ) -> Result<Self, Self::Rejection> {
+        let authenticator = match state.authenticator() {
+            Some(authenticator) => authenticator,
+            None => return Ok(Self(RecipientId::Anonymous)),
+        };
+
+        let token = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(bearer_token)
+            .ok_or(AuthenticationError::MissingToken)?;
+        let recipient = authenticator.authenticate(token).await?;
+        Ok(Self(recipient))
+    }
+}
+
+fn bearer_token(header: &str) -> Option<&str> {
+    let (scheme, token) = header.split_once(' ')?;
+    let token = token.trim();
+    if !scheme.eq_ignore_ascii_case("bearer") || token.is_empty() {
+        return None;
+    }
+    Some(token)
+}
+
+impl Deref for Recipient {
+    type Target = RecipientId;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, PartialEq)]
 pub struct Pagination(pub ListCursor);
 
@@ -84,16 +139,121 @@ pub struct TableDataParams {
     #[serde(default)]
     predicate_hints: Vec<String>,
     limit_hint: Option<i32>,
-    version: Option<i32>,
+    version: Option<u64>,
+    timestamp: Option<DateTime<Utc>>,
     json_predicate_hints: Option<String>,
+    starting_version: Option<u64>,
+    ending_version: Option<u64>,
+    max_files: Option<u32>,
+    page_token: Option<String>,
+    include_refresh_token: Option<bool>,
+    refresh_token: Option<String>,
+}
+
+impl TableDataParams {
+    /// Combine the parameters into a query for the data files of a table.
+    pub fn query(&self) -> Result<TableDataQuery, ServerError> {
+        let version = match self.starting_version()? {
+            Some((start, end)) => TableDataVersion::Since { start, end },
+            None => TableDataVersion::Snapshot(self.version()?),
+        };
+        Ok(TableDataQuery {
+            version,
+            limit: self.limit(),
+            predicate: self.predicate()?,
+            page: file_page(self.max_files, self.page_token.clone())?,
+            include_refresh_token: self.include_refresh_token.unwrap_or_default(),
+            refresh_token: self.refresh_token.clone(),
+        })
+    }
+
+    pub fn version(&self) -> Result<Version, ServerError> {
+        match (self.version, self.timestamp) {
+            (None, None) => Ok(Version::Latest),
+            (Some(version), None) => Ok(Version::Number(version)),
+            (None, Some(ts)) => Ok(Version::Timestamp(ts)),
+            (Some(_), Some(_)) => Err(ServerError::InvalidTableVersion),
+        }
+    }
+
+    /// Retrieve the range of table versions of a streaming query, which
+    /// returns the data files added since the starting version. An open range
+    /// ends at the latest table version.
+    pub fn starting_version(&self) -> Result<Option<(u64, Option<u64>)>, ServerError> {
+        match (self.starting_version, self.ending_version) {
+            (None, None) => Ok(None),
+            (Some(_), _) if self.version.is_some() || self.timestamp.is_some() => {
+                Err(ServerError::InvalidTableVersion)
+            }
+            (Some(start), Some(end)) if start > end => Err(ServerError::InvalidTableVersionRange {
+                reason: "starting table version cannot be higher than ending table version"
+                    .to_string(),
+            }),
+            (Some(start), end) => Ok(Some((start, end))),
+            (None, Some(_)) => Err(ServerError::InvalidTableVersionRange {
+                reason: "ending table version requires a starting table version".to_string(),
+            }),
+        }
+    }
+
+    pub fn limit(&self) -> Option<u64> {
+        self.limit_hint.and_then(|limit| u64::try_from(limit).ok())
+    }
+
+    /// Parse the predicate hints of the request.
+    ///
+    /// Predicate hints are best effort, so hints that cannot be parsed or
+    /// are not supported are ignored instead of failing the request.
+    pub fn predicate(&self) -> Result<Option<Predicate>, ServerError> {
+        match self.json_predicate_hints.as_deref() {
+            None | Some("") => Ok(None),
+            Some(json) => match Predicate::from_json(json) {
+                Ok(predicate) => Ok(Some(predicate)),
+                Err(e) => {
+                    tracing::warn!(error = ?e, "ignoring invalid predicate hints");
+                    Ok(None)
+                }
+            },
+        }
+    }
 }
 
 pub type TableDataPredicates = Json<TableDataParams>;
 
+fn file_page(
+    max_files: Option<u32>,
+    page_token: Option<String>,
+) -> Result<ListCursor, ServerError> {
+    if max_files == Some(0) {
+        return Err(ServerError::InvalidPaginationParameters {
+            reason: String::from("the maximum number of files must be positive"),
+        });
+    }
+    Ok(ListCursor::new(max_files, page_token))
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct TableChangePredicates {
     version_range: TableVersionRange,
     include_historical_metadata: bool,
+    page: ListCursor,
+}
+
+impl TableChangePredicates {
+    pub fn into_query(self) -> TableChangesQuery {
+        let page = self.page.clone();
+        TableChangesQuery {
+            range: self.into_version_range(),
+            page,
+        }
+    }
+
+    pub fn into_version_range(self) -> VersionRange {
+        match self.version_range {
+            TableVersionRange::Version { start, end } => VersionRange::Version { start, end },
+            TableVersionRange::Timestamp { start, end } => VersionRange::Timestamp { start, end },
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -116,6 +276,8 @@ struct RawTableChangeParams {
     ending_version: Option<u64>,
     ending_timestamp: Option<String>,
     include_historical_metadata: Option<bool>,
+    max_files: Option<u32>,
+    page_token: Option<String>,
 }
 
 #[async_trait]
@@ -177,19 +339,151 @@ where
         Ok(TableChangePredicates {
             version_range: range,
             include_historical_metadata: v.include_historical_metadata.unwrap_or_default(),
+            page: file_page(v.max_files, v.page_token)?,
         })
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientCapabilities(pub Capabilities);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientCapabilities
+where
+    S: Send + Sync,
+{
+    type Rejection = ServerError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let capabilities = parts
+            .headers
+            .get(CAPABILITIES_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(Capabilities::from_header)
+            .unwrap_or_default();
+        Ok(Self(capabilities))
+    }
+}
+
+impl Deref for ClientCapabilities {
+    type Target = Capabilities;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::auth::{static_token::StaticTokenAuthenticator, MockAuthenticator};
+    use crate::manager::MockShareReader;
+    use crate::protocol::capabilities::ResponseFormat;
     use axum::extract::FromRequest;
     use axum::http::header::CONTENT_TYPE;
     use axum::http::Request;
     use chrono::TimeZone;
+    use mockall::predicate::eq;
     use serde_json::json;
 
+    fn authenticated_state() -> Arc<SharingServerState> {
+        let mut authenticator = StaticTokenAuthenticator::new();
+        authenticator.add_token("foo_token", "foo");
+        let mut state = SharingServerState::new(Arc::new(MockShareReader::new()));
+        state.set_authenticator(Arc::new(authenticator));
+        Arc::new(state)
+    }
+
+    #[tokio::test]
+    async fn extract_recipient() {
+        let state = authenticated_state();
+        let req = Request::builder()
+            .uri("http://example.com/test")
+            .header(AUTHORIZATION, "Bearer foo_token")
+            .body(())
+            .unwrap();
+        assert_eq!(
+            Recipient::from_request(req, &state).await.unwrap(),
+            Recipient(RecipientId::known("foo"))
+        );
+
+        let mut mock_authenticator = MockAuthenticator::new();
+        mock_authenticator
+            .expect_authenticate()
+            .with(eq("bar_token"))
+            .once()
+            .returning(|_| Ok(RecipientId::known("bar")));
+        let mut state = SharingServerState::new(Arc::new(MockShareReader::new()));
+        state.set_authenticator(Arc::new(mock_authenticator));
+        let req = Request::builder()
+            .uri("http://example.com/test")
+            .header(AUTHORIZATION, "bearer  bar_token")
+            .body(())
+            .unwrap();
+        assert_eq!(
+            Recipient::from_request(req, &Arc::new(state))
+                .await
+                .unwrap(),
+            Recipient(RecipientId::known("bar"))
+        );
+    }
+
+    #[tokio::test]
+    async fn extract_anonymous_recipient() {
+        let state = Arc::new(SharingServerState::new(Arc::new(MockShareReader::new())));
+        let req = Request::builder()
+            .uri("http://example.com/test")
+            .body(())
+            .unwrap();
+        assert_eq!(
+            Recipient::from_request(req, &state).await.unwrap(),
+            Recipient(RecipientId::Anonymous)
+        );
+    }
+
+    #[tokio::test]
+    async fn reject_unauthenticated_recipient() {
+        let state = authenticated_state();
+
+        // Missing authorization header
+        let req = Request::builder()
+            .uri("http://example.com/test")
+            .body(())
+            .unwrap();
+        assert_eq!(
+            Recipient::from_request(req, &state).await.unwrap_err(),
+            ServerError::Unauthenticated {
+                reason: AuthenticationError::MissingToken.to_string()
+            }
+        );
+
+        // Unsupported authorization scheme
+        let req = Request::builder()
+            .uri("http://example.com/test")
+            .header(AUTHORIZATION, "Basic Zm9vOmJhcg==")
+            .body(())
+            .unwrap();
+        assert_eq!(
+            Recipient::from_request(req, &state).await.unwrap_err(),
+            ServerError::Unauthenticated {
+                reason: AuthenticationError::MissingToken.to_string()
+            }
+        );
+
+        // Unknown bearer token
+        let req = Request::builder()
+            .uri("http://example.com/test")
+            .header(AUTHORIZATION, "Bearer baz_token")
+            .body(())
+            .unwrap();
+        assert_eq!(
+            Recipient::from_request(req, &state).await.unwrap_err(),
+            ServerError::Unauthenticated {
+                reason: AuthenticationError::InvalidToken.to_string()
+            }
+        );
+    }
+
     async fn check_pagination_ok(route: impl AsRef<str>, value: ListCursor) {
         let uri = format!("http://example.com{}", route.as_ref());
         let req = Request::builder().uri(&uri).body(()).unwrap();
@@ -283,7 +577,14 @@ mod tests {
                 predicate_hints: vec![],
                 limit_hint: Some(1000),
                 version: Some(2),
-                json_predicate_hints: Some(String::new())
+                timestamp: None,
+                json_predicate_hints: Some(String::new()),
+                starting_version: None,
+                ending_version: None,
+                max_files: None,
+                page_token: None,
+                include_refresh_token: None,
+                refresh_token: None,
             }
         );
 
@@ -297,6 +598,201 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn extract_table_data_version() {
+        let params = json!({ "version": 2 });
+        let req = Request::builder()
+            .uri("http://example.com/test")
+            .header(CONTENT_TYPE, "application/json; charset=utf-8")
+            .body(serde_json::to_string(&params).unwrap())
+            .unwrap();
+        let data_params = TableDataPredicates::from_request(req, &()).await.unwrap();
+        assert_eq!(data_params.version().unwrap(), Version::Number(2));
+
+        let params = json!({ "timestamp": "2000-01-01T00:00:00Z" });
+        let req = Request::builder()
+            .uri("http://example.com/test")
+            .header(CONTENT_TYPE, "application/json; charset=utf-8")
+            .body(serde_json::to_string(&params).unwrap())
+            .unwrap();
+        let data_params = TableDataPredicates::from_request(req, &()).await.unwrap();
+        assert_eq!(
+            data_params.version().unwrap(),
+            Version::Timestamp(Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap())
+        );
+
+        let params = json!({ "version": 2, "timestamp": "2000-01-01T00:00:00Z" });
+        let req = Request::builder()
+            .uri("http://example.com/test")
+            .header(CONTENT_TYPE, "application/json; charset=utf-8")
+            .body(serde_json::to_string(&params).unwrap())
+            .unwrap();
+        let data_params = TableDataPredicates::from_request(req, &()).await.unwrap();
+        assert_eq!(
+            data_params.version().unwrap_err(),
+            ServerError::InvalidTableVersion
+        );
+    }
+
+    #[tokio::test]
+    async fn extract_table_data_starting_version() {
+        for (params, starting_version) in [
+            (json!({}), None),
+            (json!({ "startingVersion": 2 }), Some((2, None))),
+            (
+                json!({ "startingVersion": 2, "endingVersion": 5 }),
+                Some((2, Some(5))),
+            ),
+        ] {
+            let req = Request::builder()
+                .uri("http://example.com/test")
+                .header(CONTENT_TYPE, "application/json; charset=utf-8")
+                .body(serde_json::to_string(&params).unwrap())
+                .unwrap();
+            let data_params = TableDataPredicates::from_request(req, &()).await.unwrap();
+            assert_eq!(data_params.starting_version().unwrap(), starting_version);
+        }
+
+        for params in [
+            json!({ "startingVersion": 5, "endingVersion": 2 }),
+            json!({ "endingVersion": 2 }),
+            json!({ "startingVersion": 2, "version": 3 }),
+        ] {
+            let req = Request::builder()
+                .uri("http://example.com/test")
+                .header(CONTENT_TYPE, "application/json; charset=utf-8")
+                .body(serde_json::to_string(&params).unwrap())
+                .unwrap();
+            let data_params = TableDataPredicates::from_request(req, &()).await.unwrap();
+            assert!(data_params.starting_version().is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn extract_table_data_page() {
+        let params = json!({ "maxFiles": 10, "pageToken": "token", "version": 2 });
+        let req = Request::builder()
+            .uri("http://example.com/test")
+            .header(CONTENT_TYPE, "application/json; charset=utf-8")
+            .body(serde_json::to_string(&params).unwrap())
+            .unwrap();
+        let query = TableDataPredicates::from_request(req, &())
+            .await
+            .unwrap()
+            .query()
+            .unwrap();
+        assert_eq!(
+            query.version,
+            TableDataVersion::Snapshot(Version::Number(2))
+        );
+        assert_eq!(
+            query.page,
+            ListCursor::new(Some(10), Some(String::from("token")))
+        );
+
+        let params = json!({ "maxFiles": 0 });
+        let req = Request::builder()
+            .uri("http://example.com/test")
+            .header(CONTENT_TYPE, "application/json; charset=utf-8")
+            .body(serde_json::to_string(&params).unwrap())
+            .unwrap();
+        let data_params = TableDataPredicates::from_request(req, &()).await.unwrap();
+        assert!(matches!(
+            data_params.query(),
+            Err(ServerError::InvalidPaginationParameters { .. })
+        ));
+
+        let req = Request::builder()
+            .uri("http://example.com/test?startingVersion=0&endingVersion=2&maxFiles=5&pageToken=token")
+            .body(())
+            .unwrap();
+        let query = TableChangePredicates::from_request(req, &())
+            .await
+            .unwrap()
+            .into_query();
+        assert_eq!(query.range, VersionRange::Version { start: 0, end: 2 });
+        assert_eq!(
+            query.page,
+            ListCursor::new(Some(5), Some(String::from("token")))
+        );
+    }
+
+    #[tokio::test]
+    async fn extract_table_data_limit() {
+        for (params, limit) in [
+            (json!({ "limitHint": 10 }), Some(10)),
+            (json!({ "limitHint": -1 }), None),
+            (json!({}), None),
+        ] {
+            let req = Request::builder()
+                .uri("http://example.com/test")
+                .header(CONTENT_TYPE, "application/json; charset=utf-8")
+                .body(serde_json::to_string(&params).unwrap())
+                .unwrap();
+            let data_params = TableDataPredicates::from_request(req, &()).await.unwrap();
+            assert_eq!(data_params.limit(), limit);
+        }
+    }
+
+    #[tokio::test]
+    async fn extract_table_data_predicate() {
+        let predicate = json!({
+            "op": "isNull",
+            "children": [{ "op": "column", "name": "date", "valueType": "date" }]
+        });
+        let params = json!({ "jsonPredicateHints": predicate.to_string() });
+        let req = Request::builder()
+            .uri("http://example.com/test")
+            .header(CONTENT_TYPE, "application/json; charset=utf-8")
+            .body(serde_json::to_string(&params).unwrap())
+            .unwrap();
+        let data_params = TableDataPredicates::from_request(req, &()).await.unwrap();
+        assert_eq!(
+            data_params.predicate().unwrap(),
+            Some(Predicate::from_json(&predicate.to_string()).unwrap())
+        );
+
+        let params = json!({ "jsonPredicateHints": "" });
+        let req = Request::builder()
+            .uri("http://example.com/test")
+            .header(CONTENT_TYPE, "application/json; charset=utf-8")
+            .body(serde_json::to_string(&params).unwrap())
+            .unwrap();
+        let data_params = TableDataPredicates::from_request(req, &()).await.unwrap();
+        assert_eq!(data_params.predicate().unwrap(), None);
+
+        let params = json!({ "jsonPredicateHints": "{\"op\":\"like\"}" });
+        let req = Request::builder()
+            .uri("http://example.com/test")
+            .header(CONTENT_TYPE, "application/json; charset=utf-8")
+            .body(serde_json::to_string(&params).unwrap())
+            .unwrap();
+        let data_params = TableDataPredicates::from_request(req, &()).await.unwrap();
+        assert_eq!(data_params.predicate().unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn extract_client_capabilities() {
+        let req = Request::builder()
+            .uri("http://example.com/test")
+            .header(
+                "Delta-Sharing-Capabilities",
+                "responseformat=delta;readerfeatures=deletionvectors",
+            )
+            .body(())
+            .unwrap();
+        let capabilities = ClientCapabilities::from_request(req, &()).await.unwrap();
+        assert_eq!(capabilities.response_formats(), &[ResponseFormat::Delta]);
+        assert!(capabilities.supports_reader_feature("deletionVectors"));
+
+        let req = Request::builder()
+            .uri("http://example.com/test")
+            .body(())
+            .unwrap();
+        let capabilities = ClientCapabilities::from_request(req, &()).await.unwrap();
+        assert_eq!(capabilities, ClientCapabilities(Capabilities::default()));
+    }
+
     #[tokio::test]
     async fn extract_table_change_params() {
         let req = Request::builder()
@@ -308,7 +804,8 @@ mod tests {
             TableChangePredicates::from_request(req, &()).await.unwrap(),
             TableChangePredicates {
                 version_range: TableVersionRange::Version { start: 0, end: 2 },
-                include_historical_metadata: false
+                include_historical_metadata: false,
+                page: ListCursor::default(),
             }
         );
 
@@ -324,7 +821,8 @@ mod tests {
                     start: Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
                     end: Utc.with_ymd_and_hms(2000, 1, 2, 0, 0, 0).unwrap()
                 },
-                include_historical_metadata: false
+                include_historical_metadata: false,
+                page: ListCursor::default(),
             }
         );
     }
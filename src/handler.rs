@@ -6,9 +6,11 @@ use axum::extract::{Path, State};
 use axum_macros::debug_handler;
 
 use crate::{
-    error::{Result, ServerError},
-    extract::{Pagination, TableChangePredicates, TableDataPredicates, TableVersion},
-    protocol::table::Version,
+    error::Result,
+    extract::{
+        ClientCapabilities, Pagination, Recipient, TableChangePredicates, TableDataPredicates,
+        TableVersion,
+    },
     response::{
         GetShareResponse, ListSchemasResponse, ListSharesResponse, ListTablesResponse,
         TableActionsResponse, TableVersionResponse,
@@ -19,87 +21,129 @@ use crate::{
 #[debug_handler]
 pub async fn list_shares(
     state: State<Arc<SharingServerState>>,
+    recipient: Recipient,
     pagination: Pagination,
 ) -> Result<ListSharesResponse> {
-    state.list_shares(&pagination).await
+    state.list_shares(&recipient, &pagination).await
 }
 
 #[debug_handler]
 pub async fn get_share(
     state: State<Arc<SharingServerState>>,
+    recipient: Recipient,
     share_name: Path<String>,
 ) -> Result<GetShareResponse> {
-    state.get_share(&share_name).await
+    state.get_share(&recipient, &share_name).await
 }
 
 #[debug_handler]
 pub async fn list_schemas(
     state: State<Arc<SharingServerState>>,
+    recipient: Recipient,
     share_name: Path<String>,
     pagination: Pagination,
 ) -> Result<ListSchemasResponse> {
-    state.list_schemas(&share_name, &pagination).await
+    state
+        .list_schemas(&recipient, &share_name, &pagination)
+        .await
 }
 
 #[debug_handler]
 pub async fn list_tables_in_share(
     state: State<Arc<SharingServerState>>,
+    recipient: Recipient,
     share_name: Path<String>,
     pagination: Pagination,
 ) -> Result<ListTablesResponse> {
-    state.list_tables_in_share(&share_name, &pagination).await
+    state
+        .list_tables_in_share(&recipient, &share_name, &pagination)
+        .await
 }
 
 #[debug_handler]
 pub async fn list_tables_in_schema(
     state: State<Arc<SharingServerState>>,
+    recipient: Recipient,
     Path((share_name, schema_name)): Path<(String, String)>,
     pagination: Pagination,
 ) -> Result<ListTablesResponse> {
     state
-        .list_tables_in_schema(&share_name, &schema_name, &pagination)
+        .list_tables_in_schema(&recipient, &share_name, &schema_name, &pagination)
         .await
 }
 
 #[debug_handler]
 pub async fn get_table_version(
     state: State<Arc<SharingServerState>>,
+    recipient: Recipient,
     Path((share_name, schema_name, table_name)): Path<(String, String, String)>,
     tv: TableVersion,
 ) -> Result<TableVersionResponse> {
     state
-        .get_table_version(&share_name, &schema_name, &table_name, tv.into_version())
+        .get_table_version(
+            &recipient,
+            &share_name,
+            &schema_name,
+            &table_name,
+            tv.into_version(),
+        )
         .await
 }
 
 #[debug_handler]
 pub async fn get_table_metadata(
     State(state): State<Arc<SharingServerState>>,
+    recipient: Recipient,
     Path((share_name, schema_name, table_name)): Path<(String, String, String)>,
+    capabilities: ClientCapabilities,
 ) -> Result<TableActionsResponse> {
     state
-        .get_table_metadata(&share_name, &schema_name, &table_name)
+        .get_table_metadata(
+            &recipient,
+            &share_name,
+            &schema_name,
+            &table_name,
+            &capabilities,
+        )
         .await
 }
 
 #[debug_handler]
 pub async fn get_table_data(
     State(state): State<Arc<SharingServerState>>,
+    recipient: Recipient,
     Path((share_name, schema_name, table_name)): Path<(String, String, String)>,
-    _predicates: TableDataPredicates,
+    capabilities: ClientCapabilities,
+    predicates: TableDataPredicates,
 ) -> Result<TableActionsResponse> {
     state
-        .get_table_data(&share_name, &schema_name, &table_name, Version::Latest)
+        .get_table_data(
+            &recipient,
+            &share_name,
+            &schema_name,
+            &table_name,
+            predicates.query()?,
+            &capabilities,
+        )
         .await
 }
 
 #[debug_handler]
 pub async fn get_table_changes(
-    State(_state): State<Arc<SharingServerState>>,
-    Path((_share_name, _schema_name, _table_name)): Path<(String, String, String)>,
-    _version_range: TableChangePredicates,
+    State(state): State<Arc<SharingServerState>>,
+    recipient: Recipient,
+    Path((share_name, schema_name, table_name)): Path<(String, String, String)>,
+    predicates: TableChangePredicates,
+    capabilities: ClientCapabilities,
 ) -> Result<TableActionsResponse> {
-    Err(ServerError::UnsupportedOperation {
-        reason: String::from("The `table_changes` endpoint is not yet implemented."),
-    })
+    state
+        .get_table_changes(
+            &recipient,
+            &share_name,
+            &schema_name,
+            &table_name,
+            predicates.into_query(),
+            &capabilities,
+        )
+        .await
 }
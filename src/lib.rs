@@ -14,15 +14,17 @@
 //!
 //! ## Design
 //!
-//! In order to provide flexibility, this crate is build around three core
+//! In order to provide flexibility, this crate is build around four core
 //! abstractions.
 //!
 //! - ShareReader: This trait is responsible for the communication between
-//! sharing server and the source system for shares, schemas and tables.
+//!   sharing server and the source system for shares, schemas and tables.
 //! - TableReader: This trait is responsible for reading tables of a specified
-//! table format
+//!   table format
 //! - UrlSigner: This trait is responsible for signing the urls that will be
-//! used to access the data.
+//!   used to access the data.
+//! - Authenticator: This trait is responsible for resolving the bearer token
+//!   of a request to the recipient that issued it.
 //!
 //! ```rust,no_run
 //! # use std::sync::Arc;
@@ -62,6 +64,7 @@
 
 #![warn(missing_docs)]
 
+pub mod auth;
 pub mod manager;
 pub mod protocol;
 pub mod reader;
@@ -70,6 +73,7 @@ pub mod signer;
 pub mod error;
 mod extract;
 mod handler;
+mod refresh_token;
 mod response;
 pub mod router;
 pub mod state;
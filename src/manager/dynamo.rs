@@ -15,14 +15,14 @@ use crate::protocol::securable::{Schema, SchemaBuilder, Share, ShareBuilder, Tab
 
 use super::{List, ListCursor, ShareIoError, ShareReader};
 
-/// ASSUMPTION: the writer only uses transactions to guarantee the consistency of the
-/// shared securables (i.e. share -> schema -> table).
-///
-/// | SHARE#ALL | share1 | ...
-/// | SHARE#ALL | share2 | ...
-/// | SHARE#share1 | schema1 | ...
-/// | SHARE#share1 | schema2 | ...
-/// | SHARE#share2 | schema1#table1 | ...
+// ASSUMPTION: the writer only uses transactions to guarantee the consistency of the
+// shared securables (i.e. share -> schema -> table).
+//
+// | SHARE#ALL | share1 | ...
+// | SHARE#ALL | share2 | ...
+// | SHARE#share1 | schema1 | ...
+// | SHARE#share1 | schema2 | ...
+// | SHARE#share2 | schema1#table1 | ...
 
 /// TableManager using AWS DynamoDB to store shared objects.
 ///
@@ -32,6 +32,7 @@ use super::{List, ListCursor, ShareIoError, ShareReader};
 /// SHARE#{share_name}#SCHEMA#ALL#TABLE#ALL | SHARE | share1_id
 /// SHARE#{share_name}#SCHEMA#{schema_name}#TABLE#ALL | SCHEMA |
 /// SHARE#{share_name}#SCHEMA#{schema_name}#TABLE#{table_name} | TABLE | share1_id | s3://my-data-bucket/my-table-root/ | table1_id
+/// RECIPIENT#{recipient_name} | GRANT#{share_name} | share_name
 ///
 ///  Key
 /// 1. KEY: PK+SK
@@ -44,6 +45,7 @@ use super::{List, ListCursor, ShareIoError, ShareReader};
 /// 4. QUERY on GSI with type = TABLE and SK begins_with(SHARE#{share_name}#SCHEMA#{schema_name})
 /// 5. QUERY on GSI with type = TABLE and SK begins_with(SHARE#{share_name})
 /// 6. GET on KEY with PK = SHARE#{share_name}#SCHEMA#{schema_name}#TABLE#{table_name} AND SK = TABLE
+/// 7. QUERY on KEY with PK = RECIPIENT#{recipient_name} AND SK begins_with(GRANT#)
 ///
 /// ## Query patterns
 /// 1. Get a table by share_name, schema_name and table_name
@@ -52,6 +54,7 @@ use super::{List, ListCursor, ShareIoError, ShareReader};
 /// 3. List all schemas in a share
 /// 4. List all tables in a share
 /// 5. List all tables in a schema
+/// 6. List all shares granted to a recipient
 ///
 #[derive(Debug)]
 pub struct DynamoShareReader {
@@ -213,6 +216,74 @@ impl DynamoShareReader {
         self.query_securable(cursor, sk, pk_prefix).await
     }
 
+    /// Grant a recipient access to a share.
+    pub async fn put_share_grant(
+        &self,
+        recipient_name: &str,
+        share_name: &str,
+    ) -> Result<(), DynamoError> {
+        self.client
+            .put_item()
+            .table_name(&self.table_name)
+            .item(
+                "PK",
+                AttributeValue::S(format!("RECIPIENT#{}", recipient_name)),
+            )
+            .item("SK", AttributeValue::S(format!("GRANT#{}", share_name)))
+            .item("share_name", AttributeValue::S(share_name.to_owned()))
+            .send()
+            .await
+            .map_err(|e| DynamoError::ServiceError {
+                reason: e.to_string(),
+            })?;
+
+        Ok(())
+    }
+
+    /// Retrieve the names of all shares granted to a recipient.
+    pub async fn query_share_grants(
+        &self,
+        recipient_name: &str,
+    ) -> Result<Vec<String>, DynamoError> {
+        let mut share_names = vec![];
+        let mut start_key = None;
+        loop {
+            let query_output = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .expression_attribute_names("#PK", "PK")
+                .expression_attribute_names("#SK", "SK")
+                .expression_attribute_values(
+                    ":pk",
+                    AttributeValue::S(format!("RECIPIENT#{}", recipient_name)),
+                )
+                .expression_attribute_values(":sk", AttributeValue::S("GRANT#".to_owned()))
+                .key_condition_expression("#PK = :pk AND begins_with(#SK, :sk)")
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await
+                .map_err(|e| DynamoError::ServiceError {
+                    reason: e.to_string(),
+                })?;
+
+            for item in query_output.items().unwrap_or_default() {
+                let share_name = item
+                    .get("share_name")
+                    .and_then(|v| v.as_s().ok())
+                    .ok_or(DynamoError::InvalidGrantItem)?;
+                share_names.push(share_name.to_owned());
+            }
+
+            match query_output.last_evaluated_key() {
+                Some(key) => start_key = Some(key.clone()),
+                None => break,
+            }
+        }
+
+        Ok(share_names)
+    }
+
     async fn get_securable<
         T: for<'a> TryFrom<&'a HashMap<String, AttributeValue>, Error = DynamoError>,
     >(
@@ -339,6 +410,8 @@ pub enum DynamoError {
     InvalidSchemaItem,
     /// The requested table could not be parsed.
     InvalidTableItem,
+    /// The requested share grant could not be parsed.
+    InvalidGrantItem,
     /// An error occurred when interacting with the DynamoDB service.
     ServiceError {
         /// The reason for the error.
@@ -667,4 +740,10 @@ impl ShareReader for DynamoShareReader {
             .await
             .map_err(From::from)
     }
+
+    async fn list_share_grants(&self, recipient_name: &str) -> Result<Vec<String>, ShareIoError> {
+        self.query_share_grants(recipient_name)
+            .await
+            .map_err(From::from)
+    }
 }
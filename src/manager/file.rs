@@ -1,8 +1,20 @@
 //! TableManager implementation using a local file.
+//!
+//! The file can be watched for changes, such that shares can be edited
+//! without restarting the server. The file is polled at a fixed interval and
+//! a changed file replaces the configuration in a single swap. An invalid
+//! file is rejected and the last valid configuration stays in use.
 
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Duration;
 
+use base64::{engine::general_purpose, Engine as _};
 use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
 
 use crate::protocol::{
     securable::{Schema, SchemaBuilder, Share, ShareBuilder, Table, TableBuilder},
@@ -14,7 +26,7 @@ use crate::manager::ShareIoError;
 use super::ShareReader;
 
 /// The file format where the share configuration is stored.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FileFormat {
     /// Json file format
     Json,
@@ -24,24 +36,98 @@ pub enum FileFormat {
     Toml,
 }
 
+impl FileFormat {
+    /// Detect the file format from the extension of the path, i.e. `.json`,
+    /// `.yaml`, `.yml` or `.toml`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "json" => Some(FileFormat::Json),
+            "yaml" | "yml" => Some(FileFormat::Yaml),
+            "toml" => Some(FileFormat::Toml),
+            _ => None,
+        }
+    }
+
+    fn deserialize(self, contents: &[u8]) -> Result<ShareConfig, String> {
+        match self {
+            FileFormat::Json => serde_json::from_slice(contents).map_err(|e| e.to_string()),
+            FileFormat::Yaml => serde_yaml::from_slice(contents).map_err(|e| e.to_string()),
+            FileFormat::Toml => std::str::from_utf8(contents)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| toml::from_str(contents).map_err(|e| e.to_string())),
+        }
+    }
+
+    fn serialize(self, config: &ShareConfig) -> Result<Vec<u8>, String> {
+        match self {
+            FileFormat::Json => serde_json::to_vec_pretty(config).map_err(|e| e.to_string()),
+            FileFormat::Yaml => serde_yaml::to_string(config)
+                .map(String::into_bytes)
+                .map_err(|e| e.to_string()),
+            FileFormat::Toml => toml::to_string(config)
+                .map(String::into_bytes)
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
 /// ShareReader using a configuration file as the backing store.
 #[derive(Debug)]
 pub struct FileShareManager {
     path: PathBuf,
     format: FileFormat,
-    share_file: ShareConfig,
+    share_file: Arc<RwLock<Arc<ShareConfig>>>,
+    watcher: Option<JoinHandle<()>>,
 }
 
 impl FileShareManager {
     /// Creates a new instance of the FileShareManager.
-    pub fn new(path: PathBuf) -> Self {
-        let shares_file = Self::read_from_file(&path).unwrap();
+    ///
+    /// The file format is detected from the extension of the file and
+    /// defaults to YAML. Returns an error if the file cannot be read or does
+    /// not contain a valid share configuration.
+    pub fn new(path: PathBuf) -> Result<Self, ShareIoError> {
+        let format = FileFormat::from_path(&path).unwrap_or(FileFormat::Yaml);
+        Self::with_format(path, format)
+    }
 
-        Self {
+    /// Creates a new instance of the FileShareManager for a file in the
+    /// given format.
+    pub fn with_format(path: PathBuf, format: FileFormat) -> Result<Self, ShareIoError> {
+        let shares_file = Self::read_from_file(&path, format)?;
+
+        Ok(Self {
             path,
-            format: FileFormat::Yaml,
-            share_file: shares_file,
+            format,
+            share_file: Arc::new(RwLock::new(Arc::new(shares_file))),
+            watcher: None,
+        })
+    }
+
+    /// Watch the file for changes by polling it at the given interval.
+    ///
+    /// A valid new configuration replaces the current one, an invalid
+    /// configuration is logged and ignored. The watcher runs on the Tokio
+    /// runtime and stops when the manager is dropped.
+    pub fn watch(mut self, poll_interval: Duration) -> Self {
+        if let Some(watcher) = self.watcher.take() {
+            watcher.abort();
         }
+
+        let path = self.path.clone();
+        let format = self.format;
+        let share_file = self.share_file.clone();
+        self.watcher = Some(tokio::spawn(async move {
+            let mut last_contents = None;
+            let mut interval = tokio::time::interval(poll_interval);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                reload(&path, format, &share_file, &mut last_contents).await;
+            }
+        }));
+        self
     }
 
     /// Returns the path to the file that contains the configuration of the shared securables.
@@ -54,32 +140,113 @@ impl FileShareManager {
         self.format
     }
 
-    fn read_from_file(path: &PathBuf) -> Result<ShareConfig, ShareIoError> {
-        let handle = std::fs::OpenOptions::new().read(true).open(path).unwrap();
-        let shares_file: ShareConfig = serde_yaml::from_reader(handle).unwrap();
-        Ok(shares_file)
+    fn config(&self) -> Arc<ShareConfig> {
+        self.share_file
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
     }
 
-    fn _write_to_file(&self) -> Result<(), ShareIoError> {
-        let handle = std::fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .open(&self.path)
-            .expect("Couldn't open file");
-        serde_yaml::to_writer(handle, &self.share_file).unwrap();
-        Ok(())
+    fn read_from_file(path: &Path, format: FileFormat) -> Result<ShareConfig, ShareIoError> {
+        let contents = std::fs::read(path).map_err(|e| ShareIoError::Other {
+            reason: format!(
+                "could not read share configuration `{}`: {}",
+                path.display(),
+                e
+            ),
+        })?;
+        parse_share_config(path, format, &contents)
+    }
+
+    /// Write the current share configuration to a file in the given format.
+    ///
+    /// This can be used to persist the configuration or to convert it to a
+    /// different file format. The file is created if it does not exist and
+    /// overwritten otherwise.
+    pub fn write_to_file(&self, path: &Path, format: FileFormat) -> Result<(), ShareIoError> {
+        let write_error = |e: &dyn std::fmt::Display| ShareIoError::Other {
+            reason: format!(
+                "could not write share configuration `{}`: {}",
+                path.display(),
+                e
+            ),
+        };
+        let contents = format
+            .serialize(&self.config())
+            .map_err(|e| write_error(&e))?;
+        std::fs::write(path, contents).map_err(|e| write_error(&e))
+    }
+}
+
+impl Drop for FileShareManager {
+    fn drop(&mut self) {
+        if let Some(watcher) = self.watcher.take() {
+            watcher.abort();
+        }
+    }
+}
+
+fn parse_share_config(
+    path: &Path,
+    format: FileFormat,
+    contents: &[u8],
+) -> Result<ShareConfig, ShareIoError> {
+    format
+        .deserialize(contents)
+        .and_then(|config| config.validate().map(|_| config))
+        .map_err(|e| ShareIoError::Other {
+            reason: format!("invalid share configuration `{}`: {}", path.display(), e),
+        })
+}
+
+/// Swap in the configuration in the file if it changed since the last poll.
+async fn reload(
+    path: &Path,
+    format: FileFormat,
+    share_file: &RwLock<Arc<ShareConfig>>,
+    last_contents: &mut Option<Vec<u8>>,
+) {
+    let contents = match tokio::fs::read(path).await {
+        Ok(contents) => contents,
+        Err(e) => {
+            tracing::warn!(
+                path = %path.display(),
+                error = %e,
+                "could not read share configuration, keeping the last valid configuration"
+            );
+            return;
+        }
+    };
+    if last_contents.as_ref() == Some(&contents) {
+        return;
+    }
+
+    match parse_share_config(path, format, &contents) {
+        Ok(config) => {
+            *share_file.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(config);
+            if last_contents.is_some() {
+                tracing::info!(path = %path.display(), "reloaded share configuration");
+            }
+        }
+        Err(e) => {
+            tracing::error!(
+                error = ?e,
+                "rejected share configuration, keeping the last valid configuration"
+            );
+        }
     }
+    *last_contents = Some(contents);
 }
 
 #[async_trait::async_trait]
 impl ShareReader for FileShareManager {
-    async fn list_shares(&self, _cursor: &ListCursor) -> Result<List<Share>, ShareIoError> {
-        let shares = self.share_file.shares();
-        Ok(List::new(shares, None))
+    async fn list_shares(&self, cursor: &ListCursor) -> Result<List<Share>, ShareIoError> {
+        let shares = self.config().shares();
+        paginate(shares, cursor)
     }
 
     async fn get_share(&self, share_name: &str) -> Result<Share, ShareIoError> {
-        self.share_file
+        self.config()
             .shares()
             .into_iter()
             .find(|share| share.name() == share_name)
@@ -91,29 +258,29 @@ impl ShareReader for FileShareManager {
     async fn list_schemas(
         &self,
         share_name: &str,
-        _cursor: &ListCursor,
+        cursor: &ListCursor,
     ) -> Result<List<Schema>, ShareIoError> {
-        let schemas = self.share_file.schemas(share_name);
-        Ok(List::new(schemas, None))
+        let schemas = self.config().schemas(share_name);
+        paginate(schemas, cursor)
     }
 
     async fn list_tables_in_share(
         &self,
         share_name: &str,
-        _cursor: &ListCursor,
+        cursor: &ListCursor,
     ) -> Result<List<Table>, ShareIoError> {
-        let tables = self.share_file.tables(share_name, None);
-        Ok(List::new(tables, None))
+        let tables = self.config().tables(share_name, None);
+        paginate(tables, cursor)
     }
 
     async fn list_tables_in_schema(
         &self,
         share_name: &str,
         schema_name: &str,
-        _cursor: &ListCursor,
+        cursor: &ListCursor,
     ) -> Result<List<Table>, ShareIoError> {
-        let tables = self.share_file.tables(share_name, Some(schema_name));
-        Ok(List::new(tables, None))
+        let tables = self.config().tables(share_name, Some(schema_name));
+        paginate(tables, cursor)
     }
 
     async fn get_table(
@@ -122,7 +289,7 @@ impl ShareReader for FileShareManager {
         schema_name: &str,
         table_name: &str,
     ) -> Result<Table, ShareIoError> {
-        let tables = self.share_file.tables(share_name, Some(schema_name));
+        let tables = self.config().tables(share_name, Some(schema_name));
         tables
             .into_iter()
             .find(|table| table.name() == table_name)
@@ -132,19 +299,158 @@ impl ShareReader for FileShareManager {
                 table_name: table_name.to_string(),
             })
     }
+
+    async fn list_share_grants(&self, recipient_name: &str) -> Result<Vec<String>, ShareIoError> {
+        Ok(self.config().share_grants(recipient_name))
+    }
+}
+
+/// Restrict a listing of shared objects to the page requested by the cursor.
+fn paginate<T: Display>(items: Vec<T>, cursor: &ListCursor) -> Result<List<T>, ShareIoError> {
+    let start = match cursor.page_token() {
+        Some(token) => FileCursor::from_token(token)?.resume_position(&items),
+        None => 0,
+    };
+    let max_results = cursor
+        .max_results()
+        .map_or(usize::MAX, |max_results| max_results as usize);
+
+    let mut page = items
+        .into_iter()
+        .skip(start)
+        .take(max_results.saturating_add(1))
+        .collect::<Vec<_>>();
+    let next_page_token = if page.len() > max_results {
+        page.truncate(max_results);
+        page.last().map(|last| {
+            FileCursor {
+                position: start + max_results,
+                name: last.to_string(),
+            }
+            .into_token()
+        })
+    } else {
+        None
+    };
+
+    Ok(List::new(page, next_page_token))
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Position in a listing of shared objects from which the next page
+/// continues.
+///
+/// The cursor holds the full name of the last listed object besides the
+/// position of the next object, such that the next page continues after the
+/// same object when the configuration was reloaded in between.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct FileCursor {
+    position: usize,
+    name: String,
+}
+
+impl FileCursor {
+    fn into_token(self) -> String {
+        let value = serde_json::to_vec(&self).expect("file cursor can be serialized");
+        general_purpose::URL_SAFE_NO_PAD.encode(value)
+    }
+
+    fn from_token(token: &str) -> Result<Self, ShareIoError> {
+        let value = general_purpose::URL_SAFE_NO_PAD
+            .decode(token)
+            .map_err(|_| ShareIoError::MalformedContinuationToken)?;
+        serde_json::from_slice(&value).map_err(|_| ShareIoError::MalformedContinuationToken)
+    }
+
+    fn resume_position<T: Display>(&self, items: &[T]) -> usize {
+        items
+            .iter()
+            .position(|item| item.to_string() == self.name)
+            .map_or(self.position, |last| last + 1)
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 struct ShareConfig {
     shares: Vec<ShareInFile>,
+    #[serde(default)]
+    recipients: Vec<RecipientInFile>,
 }
 
 impl ShareConfig {
+    /// Check that the names of the shared securables are unique and that
+    /// the recipients are granted existing shares.
+    fn validate(&self) -> Result<(), String> {
+        let mut share_names = HashSet::new();
+        let mut table_ids = HashMap::new();
+        for share in &self.shares {
+            if share.name.is_empty() {
+                return Err(String::from("a share has an empty name"));
+            }
+            if !share_names.insert(share.name.as_str()) {
+                return Err(format!("share `{}` is defined more than once", share.name));
+            }
+
+            let mut schema_names = HashSet::new();
+            for schema in &share.schemas {
+                let schema_name = format!("{}.{}", share.name, schema.name);
+                if schema.name.is_empty() {
+                    return Err(format!(
+                        "share `{}` has a schema with an empty name",
+                        share.name
+                    ));
+                }
+                if !schema_names.insert(schema.name.as_str()) {
+                    return Err(format!(
+                        "schema `{}` is defined more than once",
+                        schema_name
+                    ));
+                }
+
+                let mut table_names = HashSet::new();
+                for table in &schema.tables {
+                    let table_name = format!("{}.{}", schema_name, table.name);
+                    if table.name.is_empty() {
+                        return Err(format!(
+                            "schema `{}` has a table with an empty name",
+                            schema_name
+                        ));
+                    }
+                    if !table_names.insert(table.name.as_str()) {
+                        return Err(format!("table `{}` is defined more than once", table_name));
+                    }
+                    if table.location.is_empty() {
+                        return Err(format!("table `{}` has an empty location", table_name));
+                    }
+                    if let Some(id) = &table.id {
+                        if let Some(other) = table_ids.insert(id.as_str(), table_name.clone()) {
+                            return Err(format!(
+                                "table `{}` has the same id as table `{}`",
+                                table_name, other
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        for recipient in &self.recipients {
+            if let Some(share) = recipient
+                .shares
+                .iter()
+                .find(|share| !share_names.contains(share.as_str()))
+            {
+                return Err(format!(
+                    "recipient `{}` is granted unknown share `{}`",
+                    recipient.name, share
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
     fn shares(&self) -> Vec<Share> {
-        self.shares
-            .iter()
-            .map(|share| ShareBuilder::new(&share.name).build())
-            .collect()
+        self.shares.iter().map(ShareInFile::share).collect()
     }
 
     fn schemas(&self, share_name: &str) -> Vec<Schema> {
@@ -152,10 +458,7 @@ impl ShareConfig {
             share
                 .schemas
                 .iter()
-                .map(|schema| {
-                    let share = ShareBuilder::new(&share.name).build();
-                    SchemaBuilder::new(share, &schema.name).build()
-                })
+                .map(|schema| schema.schema(share.share()))
                 .collect()
         } else {
             vec![]
@@ -175,38 +478,104 @@ impl ShareConfig {
                     }
                 })
                 .flat_map(|schema| {
-                    schema.tables.iter().map(|table| {
-                        let share = ShareBuilder::new(&share.name).build();
-                        let schema = SchemaBuilder::new(share, &schema.name).build();
-                        TableBuilder::new(schema, &table.name, &table.location)
-                            .id(&table.id)
-                            .build()
-                    })
+                    schema
+                        .tables
+                        .iter()
+                        .map(|table| table.table(schema.schema(share.share())))
                 })
                 .collect()
         } else {
             vec![]
         }
     }
+
+    fn share_grants(&self, recipient_name: &str) -> Vec<String> {
+        self.recipients
+            .iter()
+            .filter(|recipient| recipient.name == recipient_name)
+            .flat_map(|recipient| recipient.shares.iter().cloned())
+            .collect()
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 struct ShareInFile {
     name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
     schemas: Vec<SchemaInFile>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl ShareInFile {
+    fn share(&self) -> Share {
+        ShareBuilder::new(&self.name)
+            .set_id(self.id.as_deref())
+            .set_comment(self.comment.as_deref())
+            .build()
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 struct SchemaInFile {
     name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
     tables: Vec<TableInFile>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl SchemaInFile {
+    fn schema(&self, share: Share) -> Schema {
+        SchemaBuilder::new(share, &self.name)
+            .set_id(self.id.as_deref())
+            .set_comment(self.comment.as_deref())
+            .build()
+    }
+}
+
+/// Table in the share configuration, with the keys of the configuration of
+/// the reference Delta Sharing server. As in the reference server, the
+/// history of a table is only shared when configured, and the format of a
+/// table is case insensitive.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct TableInFile {
     name: String,
     location: String,
-    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    format: Option<String>,
+    #[serde(default)]
+    history_shared: bool,
+    /// Name of `historyShared` in earlier versions of the reference server.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    cdf_enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    start_version: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+impl TableInFile {
+    fn table(&self, schema: Schema) -> Table {
+        TableBuilder::new(schema, &self.name, &self.location)
+            .set_id(self.id.as_deref())
+            .set_format(self.format.as_deref().map(str::to_ascii_uppercase))
+            .history_shared(self.history_shared || self.cdf_enabled)
+            .set_start_version(self.start_version)
+            .set_comment(self.comment.as_deref())
+            .build()
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct RecipientInFile {
+    name: String,
+    shares: Vec<String>,
 }
 
 #[cfg(test)]
@@ -253,7 +622,15 @@ mod tests {
     tables:
     - name: "table5"
       location: "s3a://<bucket-name>/<the-table-path>"
-      id: "00000000-0000-0000-0000-000000000004""#;
+      id: "00000000-0000-0000-0000-000000000004"
+recipients:
+- name: "recipient1"
+  shares:
+  - "share1"
+  - "share3"
+- name: "recipient2"
+  shares:
+  - "share2""#;
         temp_file.write_all(shares_config.as_bytes()).unwrap();
         temp_file
     }
@@ -261,7 +638,7 @@ mod tests {
     #[tokio::test]
     async fn list_shares() {
         let tempfile = setup_share_config_file();
-        let manager = FileShareManager::new(tempfile.path().to_path_buf());
+        let manager = FileShareManager::new(tempfile.path().to_path_buf()).unwrap();
         assert_eq!(
             manager.list_shares(&ListCursor::default()).await.unwrap(),
             List::new(
@@ -276,10 +653,112 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn list_shares_with_pagination() {
+        let tempfile = setup_share_config_file();
+        let manager = FileShareManager::new(tempfile.path().to_path_buf()).unwrap();
+
+        let page = manager
+            .list_shares(&ListCursor::new(Some(3), None))
+            .await
+            .unwrap();
+        assert_eq!(
+            page.items(),
+            &[
+                ShareBuilder::new("share1").build(),
+                ShareBuilder::new("share2").build(),
+                ShareBuilder::new("share3").build(),
+            ]
+        );
+        let page_token = page.next_page_token().cloned();
+        assert!(page_token.is_some());
+
+        let page = manager
+            .list_shares(&ListCursor::new(Some(3), page_token))
+            .await
+            .unwrap();
+        assert_eq!(
+            page,
+            List::new(vec![ShareBuilder::new("share4").build()], None)
+        );
+    }
+
+    #[tokio::test]
+    async fn list_tables_with_pagination() {
+        let tempfile = setup_share_config_file();
+        let manager = FileShareManager::new(tempfile.path().to_path_buf()).unwrap();
+
+        let page = manager
+            .list_tables_in_share("share1", &ListCursor::new(Some(1), None))
+            .await
+            .unwrap();
+        assert_eq!(page.items().len(), 1);
+        assert_eq!(page.items()[0].name(), "table1");
+
+        let page = manager
+            .list_tables_in_schema(
+                "share1",
+                "schema1",
+                &ListCursor::new(Some(1), page.next_page_token().cloned()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(page.items().len(), 1);
+        assert_eq!(page.items()[0].name(), "table2");
+        assert!(page.next_page_token().is_none());
+    }
+
+    #[tokio::test]
+    async fn list_shares_with_malformed_page_token() {
+        let tempfile = setup_share_config_file();
+        let manager = FileShareManager::new(tempfile.path().to_path_buf()).unwrap();
+
+        for token in ["not a token", "bm90IGpzb24"] {
+            assert_eq!(
+                manager
+                    .list_shares(&ListCursor::new(Some(3), Some(token.to_owned())))
+                    .await
+                    .unwrap_err(),
+                ShareIoError::MalformedContinuationToken
+            );
+        }
+    }
+
+    #[test]
+    fn resume_pagination_after_reload() {
+        let shares = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| ShareBuilder::new(*name).build())
+                .collect::<Vec<_>>()
+        };
+
+        let page = paginate(
+            shares(&["a", "b", "c", "d"]),
+            &ListCursor::new(Some(2), None),
+        )
+        .unwrap();
+        let page_token = page.next_page_token().cloned();
+
+        let page = paginate(
+            shares(&["x", "a", "b", "c", "d"]),
+            &ListCursor::new(Some(2), page_token.clone()),
+        )
+        .unwrap();
+        assert_eq!(page.items(), shares(&["c", "d"]).as_slice());
+
+        let page = paginate(
+            shares(&["a", "c", "d"]),
+            &ListCursor::new(Some(2), page_token),
+        )
+        .unwrap();
+        assert_eq!(page.items(), shares(&["d"]).as_slice());
+    }
+
     #[tokio::test]
     async fn get_share() {
         let tempfile = setup_share_config_file();
-        let manager = FileShareManager::new(tempfile.path().to_path_buf());
+        let manager = FileShareManager::new(tempfile.path().to_path_buf()).unwrap();
         assert_eq!(
             manager.get_share("share1").await.unwrap(),
             ShareBuilder::new("share1").build()
@@ -289,7 +768,7 @@ mod tests {
     #[tokio::test]
     async fn list_schemas() {
         let tempfile = setup_share_config_file();
-        let manager = FileShareManager::new(tempfile.path().to_path_buf());
+        let manager = FileShareManager::new(tempfile.path().to_path_buf()).unwrap();
         assert_eq!(
             manager
                 .list_schemas("share1", &ListCursor::default())
@@ -305,7 +784,7 @@ mod tests {
     #[tokio::test]
     async fn list_tables_in_share() {
         let tempfile = setup_share_config_file();
-        let manager = FileShareManager::new(tempfile.path().to_path_buf());
+        let manager = FileShareManager::new(tempfile.path().to_path_buf()).unwrap();
         assert_eq!(
             manager
                 .list_tables_in_share("share1"  , &ListCursor::default())
@@ -319,6 +798,7 @@ mod tests {
                         "s3a://<bucket-name>/<the-table-path>"
                     )
                     .id("00000000-0000-0000-0000-000000000000")
+                    .history_shared(false)
                     .build(),
                     TableBuilder::new(
                         SchemaBuilder::new(ShareBuilder::new("share1").build(), "schema1").build(),
@@ -326,6 +806,7 @@ mod tests {
                         "wasbs://<container-name>@<account-name}.blob.core.windows.net/<the-table-path>"
                     )
                     .id("00000000-0000-0000-0000-000000000001")
+                    .history_shared(false)
                     .build(),
                 ],
                 None
@@ -336,7 +817,7 @@ mod tests {
     #[tokio::test]
     async fn list_tables_in_schema() {
         let tempfile = setup_share_config_file();
-        let manager = FileShareManager::new(tempfile.path().to_path_buf());
+        let manager = FileShareManager::new(tempfile.path().to_path_buf()).unwrap();
         assert_eq!(
             manager
                 .list_tables_in_schema("share1", "schema1", &ListCursor::default())
@@ -350,6 +831,7 @@ mod tests {
                         "s3a://<bucket-name>/<the-table-path>"
                     )
                     .id("00000000-0000-0000-0000-000000000000")
+                    .history_shared(false)
                     .build(),
                     TableBuilder::new(
                         SchemaBuilder::new(ShareBuilder::new("share1").build(), "schema1").build(),
@@ -357,6 +839,7 @@ mod tests {
                         "wasbs://<container-name>@<account-name}.blob.core.windows.net/<the-table-path>"
                     )
                     .id("00000000-0000-0000-0000-000000000001")
+                    .history_shared(false)
                     .build(),
                 ],
                 None
@@ -367,7 +850,7 @@ mod tests {
     #[tokio::test]
     async fn get_table() {
         let tempfile = setup_share_config_file();
-        let manager = FileShareManager::new(tempfile.path().to_path_buf());
+        let manager = FileShareManager::new(tempfile.path().to_path_buf()).unwrap();
         assert_eq!(
             manager
                 .get_table("share1", "schema1", "table1")
@@ -379,7 +862,358 @@ mod tests {
                 "s3a://<bucket-name>/<the-table-path>"
             )
             .id("00000000-0000-0000-0000-000000000000")
+            .history_shared(false)
             .build()
         );
     }
+
+    #[tokio::test]
+    async fn table_format_is_case_insensitive() {
+        let mut tempfile = NamedTempFile::new().unwrap();
+        let shares_config = r#"shares:
+- name: "share1"
+  schemas:
+  - name: "schema1"
+    tables:
+    - name: "table1"
+      location: "s3a://<bucket-name>/<the-table-path>"
+      format: "delta"
+    - name: "table2"
+      location: "s3a://<bucket-name>/<the-table-path>"
+      format: "Parquet""#;
+        tempfile.write_all(shares_config.as_bytes()).unwrap();
+        let manager = FileShareManager::new(tempfile.path().to_path_buf()).unwrap();
+
+        for (table_name, format) in [("table1", "DELTA"), ("table2", "PARQUET")] {
+            let table = manager
+                .get_table("share1", "schema1", table_name)
+                .await
+                .unwrap();
+            assert_eq!(table.format(), format);
+        }
+    }
+
+    #[tokio::test]
+    async fn list_share_grants() {
+        let tempfile = setup_share_config_file();
+        let manager = FileShareManager::new(tempfile.path().to_path_buf()).unwrap();
+        assert_eq!(
+            manager.list_share_grants("recipient1").await.unwrap(),
+            vec!["share1".to_owned(), "share3".to_owned()]
+        );
+        assert_eq!(
+            manager.list_share_grants("recipient2").await.unwrap(),
+            vec!["share2".to_owned()]
+        );
+        assert!(manager
+            .list_share_grants("unknown_recipient")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    fn setup_full_share_config_file() -> NamedTempFile {
+        let mut tempfile = NamedTempFile::new().unwrap();
+        let shares_config = r#"shares:
+- name: "share1"
+  id: "edacc4a7-6600-4fbb-85f3-a62a5ce6761f"
+  comment: "shared with partners"
+  schemas:
+  - name: "schema1"
+    id: "dcb1e680-7da4-4041-9be8-88aff508d001"
+    comment: "sales data"
+    tables:
+    - name: "table1"
+      location: "s3a://<bucket-name>/<the-table-path>"
+      id: "00000000-0000-0000-0000-000000000000"
+      format: "PARQUET"
+      historyShared: true
+      startVersion: 3
+      comment: "daily orders"
+    - name: "table2"
+      location: "s3a://<bucket-name>/<the-table-path>"
+      cdfEnabled: true
+    - name: "table3"
+      location: "s3a://<bucket-name>/<the-table-path>""#;
+        tempfile.write_all(shares_config.as_bytes()).unwrap();
+        tempfile
+    }
+
+    #[tokio::test]
+    async fn read_full_share_config() {
+        let tempfile = setup_full_share_config_file();
+        let manager = FileShareManager::new(tempfile.path().to_path_buf()).unwrap();
+
+        let share = ShareBuilder::new("share1")
+            .id("edacc4a7-6600-4fbb-85f3-a62a5ce6761f")
+            .comment("shared with partners")
+            .build();
+        let schema = SchemaBuilder::new(share.clone(), "schema1")
+            .id("dcb1e680-7da4-4041-9be8-88aff508d001")
+            .comment("sales data")
+            .build();
+        assert_eq!(manager.get_share("share1").await.unwrap(), share);
+        assert_eq!(
+            manager
+                .list_schemas("share1", &ListCursor::default())
+                .await
+                .unwrap(),
+            List::new(vec![schema.clone()], None)
+        );
+        assert_eq!(
+            manager
+                .list_tables_in_schema("share1", "schema1", &ListCursor::default())
+                .await
+                .unwrap(),
+            List::new(
+                vec![
+                    TableBuilder::new(
+                        schema.clone(),
+                        "table1",
+                        "s3a://<bucket-name>/<the-table-path>"
+                    )
+                    .id("00000000-0000-0000-0000-000000000000")
+                    .format("PARQUET")
+                    .history_shared(true)
+                    .start_version(3)
+                    .comment("daily orders")
+                    .build(),
+                    TableBuilder::new(
+                        schema.clone(),
+                        "table2",
+                        "s3a://<bucket-name>/<the-table-path>"
+                    )
+                    .history_shared(true)
+                    .build(),
+                    TableBuilder::new(schema, "table3", "s3a://<bucket-name>/<the-table-path>")
+                        .history_shared(false)
+                        .build(),
+                ],
+                None
+            )
+        );
+    }
+
+    #[test]
+    fn detect_file_format() {
+        assert_eq!(
+            FileFormat::from_path(Path::new("shares.json")),
+            Some(FileFormat::Json)
+        );
+        assert_eq!(
+            FileFormat::from_path(Path::new("shares.YML")),
+            Some(FileFormat::Yaml)
+        );
+        assert_eq!(
+            FileFormat::from_path(Path::new("/etc/shares.toml")),
+            Some(FileFormat::Toml)
+        );
+        assert_eq!(FileFormat::from_path(Path::new("shares")), None);
+    }
+
+    #[test]
+    fn write_and_read_share_config_file() {
+        let tempfile = setup_share_config_file();
+        let manager = FileShareManager::new(tempfile.path().to_path_buf()).unwrap();
+
+        for format in [FileFormat::Json, FileFormat::Yaml, FileFormat::Toml] {
+            let target = NamedTempFile::new().unwrap();
+            manager.write_to_file(target.path(), format).unwrap();
+
+            let reread =
+                FileShareManager::with_format(target.path().to_path_buf(), format).unwrap();
+            assert_eq!(reread.config(), manager.config());
+        }
+    }
+
+    #[test]
+    fn write_full_share_config_file() {
+        let tempfile = setup_full_share_config_file();
+        let manager = FileShareManager::new(tempfile.path().to_path_buf()).unwrap();
+
+        for format in [FileFormat::Json, FileFormat::Yaml, FileFormat::Toml] {
+            let target = NamedTempFile::new().unwrap();
+            manager.write_to_file(target.path(), format).unwrap();
+
+            let reread =
+                FileShareManager::with_format(target.path().to_path_buf(), format).unwrap();
+            assert_eq!(reread.config(), manager.config());
+        }
+    }
+
+    #[test]
+    fn write_share_config_file_to_missing_directory() {
+        let tempfile = setup_share_config_file();
+        let manager = FileShareManager::new(tempfile.path().to_path_buf()).unwrap();
+
+        let path = tempfile.path().with_extension("d").join("shares.yaml");
+        let result = manager.write_to_file(&path, FileFormat::Yaml);
+        assert!(matches!(result, Err(ShareIoError::Other { .. })));
+    }
+
+    #[test]
+    fn read_json_share_config_file() {
+        let mut tempfile = tempfile::Builder::new().suffix(".json").tempfile().unwrap();
+        let shares_config = r#"{
+  "shares": [
+    {
+      "name": "share1",
+      "schemas": [
+        {
+          "name": "schema1",
+          "tables": [
+            {
+              "name": "table1",
+              "location": "s3a://<bucket-name>/<the-table-path>",
+              "id": "00000000-0000-0000-0000-000000000000"
+            }
+          ]
+        }
+      ]
+    }
+  ]
+}"#;
+        tempfile.write_all(shares_config.as_bytes()).unwrap();
+
+        let manager = FileShareManager::new(tempfile.path().to_path_buf()).unwrap();
+        assert_eq!(manager.format(), FileFormat::Json);
+        assert_eq!(manager.config().tables("share1", None).len(), 1);
+    }
+
+    #[test]
+    fn read_toml_share_config_file() {
+        let mut tempfile = tempfile::Builder::new().suffix(".toml").tempfile().unwrap();
+        let shares_config = r#"[[shares]]
+name = "share1"
+
+[[shares.schemas]]
+name = "schema1"
+
+[[shares.schemas.tables]]
+name = "table1"
+location = "s3a://<bucket-name>/<the-table-path>"
+id = "00000000-0000-0000-0000-000000000000"
+
+[[recipients]]
+name = "recipient1"
+shares = ["share1"]
+"#;
+        tempfile.write_all(shares_config.as_bytes()).unwrap();
+
+        let manager = FileShareManager::new(tempfile.path().to_path_buf()).unwrap();
+        assert_eq!(manager.format(), FileFormat::Toml);
+        assert_eq!(manager.config().tables("share1", None).len(), 1);
+        assert_eq!(manager.config().share_grants("recipient1"), vec!["share1"]);
+    }
+
+    #[test]
+    fn validate_share_config() {
+        let invalid_config = |contents: &str| {
+            let mut tempfile = NamedTempFile::new().unwrap();
+            tempfile.write_all(contents.as_bytes()).unwrap();
+            match FileShareManager::new(tempfile.path().to_path_buf()) {
+                Err(ShareIoError::Other { reason }) => reason,
+                other => panic!("expected an invalid configuration, got {:?}", other),
+            }
+        };
+
+        let reason = invalid_config(
+            r#"shares:
+- name: "share1"
+  schemas:
+  - name: "schema1"
+    tables:
+    - name: "table1"
+      location: "s3a://<bucket-name>/<the-table-path>"
+      id: "00000000-0000-0000-0000-000000000000"
+    - name: "table1"
+      location: "s3a://<bucket-name>/<the-table-path>"
+      id: "00000000-0000-0000-0000-000000000001""#,
+        );
+        assert!(reason.contains("table `share1.schema1.table1` is defined more than once"));
+
+        let reason = invalid_config(
+            r#"shares:
+- name: "share1"
+  schemas:
+  - name: "schema1"
+    tables:
+    - name: "table1"
+      location: ""
+      id: "00000000-0000-0000-0000-000000000000""#,
+        );
+        assert!(reason.contains("table `share1.schema1.table1` has an empty location"));
+
+        let reason = invalid_config(
+            r#"shares:
+- name: "share1"
+  schemas: []
+- name: "share1"
+  schemas: []"#,
+        );
+        assert!(reason.contains("share `share1` is defined more than once"));
+
+        let reason = invalid_config(
+            r#"shares:
+- name: "share1"
+  schemas: []
+recipients:
+- name: "recipient1"
+  shares:
+  - "share2""#,
+        );
+        assert!(reason.contains("recipient `recipient1` is granted unknown share `share2`"));
+    }
+
+    #[test]
+    fn invalid_share_config_file() {
+        let missing = FileShareManager::new(PathBuf::from("/does/not/exist.yaml"));
+        assert!(matches!(missing, Err(ShareIoError::Other { .. })));
+
+        let mut tempfile = NamedTempFile::new().unwrap();
+        tempfile.write_all(b"shares: not a list").unwrap();
+        let invalid = FileShareManager::new(tempfile.path().to_path_buf());
+        assert!(matches!(invalid, Err(ShareIoError::Other { .. })));
+    }
+
+    #[tokio::test]
+    async fn reload_changed_share_config_file() {
+        let tempfile = setup_share_config_file();
+        let manager = FileShareManager::new(tempfile.path().to_path_buf())
+            .unwrap()
+            .watch(Duration::from_millis(10));
+        assert!(manager.get_share("share5").await.is_err());
+
+        std::fs::write(
+            tempfile.path(),
+            "shares:\n- name: \"share5\"\n  schemas: []\n",
+        )
+        .unwrap();
+        wait_for(&manager, "share5").await;
+        assert!(manager.get_share("share1").await.is_err());
+
+        std::fs::write(tempfile.path(), "shares: not a list").unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(
+            manager.get_share("share5").await.unwrap(),
+            ShareBuilder::new("share5").build()
+        );
+
+        std::fs::write(
+            tempfile.path(),
+            "shares:\n- name: \"share6\"\n  schemas: []\n",
+        )
+        .unwrap();
+        wait_for(&manager, "share6").await;
+    }
+
+    async fn wait_for(manager: &FileShareManager, share_name: &str) {
+        for _ in 0..100 {
+            if manager.get_share(share_name).await.is_ok() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("share `{}` was not loaded", share_name);
+    }
 }
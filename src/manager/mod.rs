@@ -64,6 +64,10 @@ pub trait ShareReader: Send + Sync {
         schema_name: &str,
         table_name: &str,
     ) -> Result<Table, ShareIoError>;
+
+    /// Fetch the names of the shares that are granted to a recipient. A
+    /// recipient that is unknown to the backing store is granted no shares.
+    async fn list_share_grants(&self, recipient_name: &str) -> Result<Vec<String>, ShareIoError>;
 }
 
 /// Trait implemented by Share managers that each represent a different backing
@@ -322,6 +322,47 @@ impl MySqlShareReader {
             .await?;
         Ok(())
     }
+
+    /// Grant a recipient access to a share.
+    pub async fn insert_share_grant(
+        &self,
+        recipient_name: &str,
+        share: &Share,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO share_grant (recipient_name, share_id) VALUES (?, ?);")
+            .bind(recipient_name)
+            .bind(share.id().unwrap())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn select_share_grants(&self, recipient_name: &str) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query(
+            r#"
+            SELECT
+                share.name AS share_name
+            FROM share_grant
+            INNER JOIN share ON share.id = share_grant.share_id
+            WHERE share_grant.recipient_name = ?
+            ORDER BY share.name;
+            "#,
+        )
+        .bind(recipient_name)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| row.try_get("share_name"))
+        .collect()
+    }
+
+    /// Delete all share grants from the database.
+    pub async fn delete_share_grants(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM share_grant;")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -554,4 +595,9 @@ impl ShareReader for MySqlShareReader {
             Err(err) => Err(err.into()),
         }
     }
+
+    async fn list_share_grants(&self, recipient_name: &str) -> Result<Vec<String>, ShareIoError> {
+        let grants = self.select_share_grants(recipient_name).await?;
+        Ok(grants)
+    }
 }
@@ -383,6 +383,54 @@ impl PostgresShareReader {
 
         Ok(())
     }
+
+    /// Grant a recipient access to a share.
+    pub async fn insert_share_grant(
+        &self,
+        recipient_name: &str,
+        share: &Share,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO share_grant (recipient_name, share_id)
+            VALUES ($1, $2);
+            "#,
+        )
+        .bind(recipient_name)
+        .bind(Uuid::parse_str(share.id().unwrap()).unwrap())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn select_share_grants(&self, recipient_name: &str) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query(
+            r#"
+            SELECT
+                share.name AS share_name
+            FROM share_grant
+            INNER JOIN share ON share.id = share_grant.share_id
+            WHERE share_grant.recipient_name = $1
+            ORDER BY share.name;
+            "#,
+        )
+        .bind(recipient_name)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| row.try_get("share_name"))
+        .collect()
+    }
+
+    /// Delete all share grants from the database.
+    pub async fn delete_share_grants(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM share_grant;")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
 }
 
 struct PostgresCursor {
@@ -606,6 +654,11 @@ impl ShareReader for PostgresShareReader {
             Err(err) => Err(err.into()),
         }
     }
+
+    async fn list_share_grants(&self, recipient_name: &str) -> Result<Vec<String>, ShareIoError> {
+        let grants = self.select_share_grants(recipient_name).await?;
+        Ok(grants)
+    }
 }
 
 // TODO: Sort out Error handling and conversion
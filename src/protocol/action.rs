@@ -3,6 +3,7 @@
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 /// Representation of the table protocol.
 ///
@@ -18,6 +19,8 @@ use serde::{Deserialize, Serialize};
 pub struct Protocol {
     /// The minimum version of the protocol that the client must support.
     min_reader_version: u32,
+    #[serde(skip)]
+    delta_protocol: Option<DeltaProtocol>,
 }
 
 impl Protocol {
@@ -26,11 +29,18 @@ impl Protocol {
     pub fn min_reader_version(&self) -> u32 {
         self.min_reader_version
     }
+
+    /// Retrieve the protocol of the underlying Delta table, if the table is
+    /// stored in the Delta Lake format.
+    pub fn delta_protocol(&self) -> Option<&DeltaProtocol> {
+        self.delta_protocol.as_ref()
+    }
 }
 
 /// Configure a protocol action.
 pub struct ProtocolBuilder {
     min_reader_version: u32,
+    delta_protocol: Option<DeltaProtocol>,
 }
 
 impl ProtocolBuilder {
@@ -38,6 +48,7 @@ impl ProtocolBuilder {
     pub fn new() -> Self {
         Self {
             min_reader_version: 1,
+            delta_protocol: None,
         }
     }
 
@@ -58,10 +69,18 @@ impl ProtocolBuilder {
         self
     }
 
+    /// Set the protocol of the underlying Delta table. It is used to answer
+    /// clients that request the Delta response format.
+    pub fn delta_protocol(mut self, delta_protocol: DeltaProtocol) -> Self {
+        self.delta_protocol = Some(delta_protocol);
+        self
+    }
+
     /// Build the configured protocol action.
     pub fn build(self) -> Protocol {
         Protocol {
             min_reader_version: self.min_reader_version,
+            delta_protocol: self.delta_protocol,
         }
     }
 }
@@ -72,6 +91,85 @@ impl Default for ProtocolBuilder {
     }
 }
 
+/// Representation of the protocol action in the transaction log of a Delta
+/// table.
+///
+/// Tables with reader version 3 list the table features a reader must
+/// support explicitly. Older reader versions imply their table features.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaProtocol {
+    min_reader_version: u32,
+    min_writer_version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reader_features: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    writer_features: Option<Vec<String>>,
+}
+
+impl DeltaProtocol {
+    /// Create a new Delta protocol without explicit table features.
+    pub fn new(min_reader_version: u32, min_writer_version: u32) -> Self {
+        Self {
+            min_reader_version,
+            min_writer_version,
+            reader_features: None,
+            writer_features: None,
+        }
+    }
+
+    /// Set the table features that a reader must support.
+    pub fn with_reader_features(mut self, reader_features: Vec<String>) -> Self {
+        self.reader_features = Some(reader_features);
+        self
+    }
+
+    /// Set the table features that a writer must support.
+    pub fn with_writer_features(mut self, writer_features: Vec<String>) -> Self {
+        self.writer_features = Some(writer_features);
+        self
+    }
+
+    /// Retrieve the minimum Delta reader version.
+    pub fn min_reader_version(&self) -> u32 {
+        self.min_reader_version
+    }
+
+    /// Retrieve the minimum Delta writer version.
+    pub fn min_writer_version(&self) -> u32 {
+        self.min_writer_version
+    }
+
+    /// Retrieve the table features that a reader must support, including the
+    /// features implied by the reader version.
+    ///
+    /// # Example
+    /// ```
+    /// use delta_sharing_server::protocol::action::DeltaProtocol;
+    ///
+    /// let protocol = DeltaProtocol::new(2, 5);
+    /// assert_eq!(protocol.reader_features(), vec!["columnMapping"]);
+    ///
+    /// let protocol = DeltaProtocol::new(3, 7)
+    ///     .with_reader_features(vec![String::from("deletionVectors")]);
+    /// assert_eq!(protocol.reader_features(), vec!["deletionVectors"]);
+    /// ```
+    pub fn reader_features(&self) -> Vec<&str> {
+        match (&self.reader_features, self.min_reader_version) {
+            (Some(features), _) => features.iter().map(String::as_str).collect(),
+            (None, 2) => vec!["columnMapping"],
+            (None, _) => vec![],
+        }
+    }
+
+    /// Determine whether the table has the given reader feature.
+    pub fn has_reader_feature(&self, feature: &str) -> bool {
+        self.reader_features()
+            .iter()
+            .any(|f| f.eq_ignore_ascii_case(feature))
+    }
+}
+
 /// Representation of the table metadata.
 ///
 /// The metadata of a table contains all the information required to correctly
@@ -95,6 +193,8 @@ pub struct Metadata {
     size: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     num_files: Option<u64>,
+    #[serde(skip)]
+    delta_metadata: Option<Value>,
 }
 
 impl Metadata {
@@ -147,6 +247,12 @@ impl Metadata {
     pub fn num_files(&self) -> Option<u64> {
         self.num_files
     }
+
+    /// Retrieve the metadata action of the underlying Delta table, if the
+    /// table is stored in the Delta Lake format.
+    pub fn delta_metadata(&self) -> Option<&Value> {
+        self.delta_metadata.as_ref()
+    }
 }
 
 /// Representation of the table format.
@@ -169,6 +275,7 @@ pub struct MetadataBuilder {
     version: Option<String>,
     size: Option<u64>,
     num_files: Option<u64>,
+    delta_metadata: Option<Value>,
 }
 
 impl MetadataBuilder {
@@ -187,6 +294,7 @@ impl MetadataBuilder {
             version: None,
             size: None,
             num_files: None,
+            delta_metadata: None,
         }
     }
 
@@ -238,6 +346,13 @@ impl MetadataBuilder {
         self
     }
 
+    /// Set the metadata action of the underlying Delta table. It is used to
+    /// answer clients that request the Delta response format.
+    pub fn delta_metadata(mut self, delta_metadata: Value) -> Self {
+        self.delta_metadata = Some(delta_metadata);
+        self
+    }
+
     /// Build the Metadata.
     pub fn build(self) -> Metadata {
         Metadata {
@@ -251,6 +366,7 @@ impl MetadataBuilder {
             version: self.version,
             size: self.size,
             num_files: self.num_files,
+            delta_metadata: self.delta_metadata,
         }
     }
 }
@@ -271,6 +387,10 @@ pub struct File {
     timestamp: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     expiration_timestamp: Option<String>,
+    #[serde(skip)]
+    delta_action: Option<Value>,
+    #[serde(skip)]
+    deletion_vector: Option<DeletionVectorFile>,
 }
 
 impl File {
@@ -320,6 +440,29 @@ impl File {
     pub fn expiration_timestamp(&self) -> Option<&str> {
         self.expiration_timestamp.as_deref()
     }
+
+    /// A mutable unix timestamp in milliseconds corresponding to the
+    /// expiration of the url associated with this file.
+    pub fn expiration_timestamp_mut(&mut self) -> &mut Option<String> {
+        &mut self.expiration_timestamp
+    }
+
+    /// The `add` action of the underlying Delta table that describes this
+    /// file, if the table is stored in the Delta Lake format.
+    pub fn delta_action(&self) -> Option<&Value> {
+        self.delta_action.as_ref()
+    }
+
+    /// The deletion vector file that marks rows of this file as deleted.
+    pub fn deletion_vector(&self) -> Option<&DeletionVectorFile> {
+        self.deletion_vector.as_ref()
+    }
+
+    /// The mutable deletion vector file that marks rows of this file as
+    /// deleted.
+    pub fn deletion_vector_mut(&mut self) -> Option<&mut DeletionVectorFile> {
+        self.deletion_vector.as_mut()
+    }
 }
 
 /// Build a new File action
@@ -332,6 +475,8 @@ pub struct FileBuilder {
     version: Option<u64>,
     timestamp: Option<String>,
     expiration_timestamp: Option<String>,
+    delta_action: Option<Value>,
+    deletion_vector: Option<DeletionVectorFile>,
 }
 
 impl FileBuilder {
@@ -346,6 +491,8 @@ impl FileBuilder {
             version: None,
             timestamp: None,
             expiration_timestamp: None,
+            delta_action: None,
+            deletion_vector: None,
         }
     }
 
@@ -397,6 +544,18 @@ impl FileBuilder {
         self
     }
 
+    /// Set the action of the underlying Delta table that describes this file.
+    pub fn delta_action(mut self, delta_action: Value) -> Self {
+        self.delta_action = Some(delta_action);
+        self
+    }
+
+    /// Set the deletion vector file that marks rows of this file as deleted.
+    pub fn deletion_vector(mut self, id: impl Into<String>, url: impl Into<String>) -> Self {
+        self.deletion_vector = Some(DeletionVectorFile::new(id, url));
+        self
+    }
+
     /// Build a File from the provided configuration.
     pub fn build(self) -> File {
         File {
@@ -408,10 +567,48 @@ impl FileBuilder {
             version: self.version,
             timestamp: self.timestamp,
             expiration_timestamp: self.expiration_timestamp,
+            delta_action: self.delta_action,
+            deletion_vector: self.deletion_vector,
         }
     }
 }
 
+/// Reference to a deletion vector file that marks rows of a data file as
+/// deleted.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletionVectorFile {
+    id: String,
+    url: String,
+}
+
+impl DeletionVectorFile {
+    /// Create a reference to a deletion vector file.
+    pub fn new(id: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            url: url.into(),
+        }
+    }
+
+    /// A unique identifier for the deletion vector file in the table.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// An url that a client can use to directly read the deletion vector
+    /// file.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// A mutable url that a client can use to directly read the deletion
+    /// vector file.
+    pub fn url_mut(&mut self) -> &mut String {
+        &mut self.url
+    }
+}
+
 /// Representation of data that was added to a table.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
@@ -425,6 +622,7 @@ pub struct Add {
     /// The size of the file in bytes.
     pub size: u64,
     /// Summary statistics about the data in this file.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub stats: Option<String>,
     /// The table version associated with this file.
     pub version: u64,
@@ -433,7 +631,15 @@ pub struct Add {
     pub timestamp: String,
     /// The unix timestamp in milliseconds corresponding to the expiration of
     /// the url associated with this file.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub expiration_timestamp: Option<String>,
+    /// The action of the underlying Delta table that describes this file, if
+    /// the table is stored in the Delta Lake format.
+    #[serde(skip)]
+    pub delta_action: Option<Value>,
+    /// The deletion vector file that marks rows of this file as deleted.
+    #[serde(skip)]
+    pub deletion_vector: Option<DeletionVectorFile>,
 }
 
 /// Initialize a new AddBuilder.
@@ -446,6 +652,8 @@ pub struct AddBuilder {
     version: Option<u64>,
     timestamp: Option<String>,
     expiration_timestamp: Option<String>,
+    delta_action: Option<Value>,
+    deletion_vector: Option<DeletionVectorFile>,
 }
 
 impl AddBuilder {
@@ -460,6 +668,8 @@ impl AddBuilder {
             version: None,
             timestamp: None,
             expiration_timestamp: None,
+            delta_action: None,
+            deletion_vector: None,
         }
     }
 
@@ -476,18 +686,48 @@ impl AddBuilder {
         self
     }
 
+    /// Set the size of this file in bytes.
+    pub fn size(mut self, size: u64) -> Self {
+        self.size = Some(size);
+        self
+    }
+
     /// Set file level statistics for this file.
     pub fn stats(mut self, stats: impl Into<String>) -> Self {
         self.stats = Some(stats.into());
         self
     }
 
+    /// Set the table version in which this file was added.
+    pub fn version(mut self, version: u64) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Set the timestamp of the table version in which this file was added.
+    pub fn timestamp(mut self, ts: impl Into<String>) -> Self {
+        self.timestamp = Some(ts.into());
+        self
+    }
+
     /// Expiration timestamp for the url associated with this file.
     pub fn expiration_timestamp(mut self, ts: impl Into<String>) -> Self {
         self.expiration_timestamp = Some(ts.into());
         self
     }
 
+    /// Set the action of the underlying Delta table that describes this file.
+    pub fn delta_action(mut self, delta_action: Value) -> Self {
+        self.delta_action = Some(delta_action);
+        self
+    }
+
+    /// Set the deletion vector file that marks rows of this file as deleted.
+    pub fn deletion_vector(mut self, id: impl Into<String>, url: impl Into<String>) -> Self {
+        self.deletion_vector = Some(DeletionVectorFile::new(id, url));
+        self
+    }
+
     /// Build an Add from the provided configuration.
     pub fn build(self) -> Add {
         Add {
@@ -499,6 +739,8 @@ impl AddBuilder {
             version: self.version.unwrap_or(0),
             timestamp: self.timestamp.unwrap_or("0".to_owned()),
             expiration_timestamp: self.expiration_timestamp,
+            delta_action: self.delta_action,
+            deletion_vector: self.deletion_vector,
         }
     }
 }
@@ -516,6 +758,7 @@ pub struct Cdf {
     /// The size of the file in bytes.
     pub size: u64,
     /// Summary statistics about the data in this file.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub stats: Option<String>,
     /// The table version associated with this file.
     pub version: u64,
@@ -524,7 +767,12 @@ pub struct Cdf {
     pub timestamp: String,
     /// The unix timestamp in milliseconds corresponding to the expiration of
     /// the url associated with this file.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub expiration_timestamp: Option<String>,
+    /// The action of the underlying Delta table that describes this file, if
+    /// the table is stored in the Delta Lake format.
+    #[serde(skip)]
+    pub delta_action: Option<Value>,
 }
 
 /// Initialize a new CdfBuilder.
@@ -537,6 +785,7 @@ pub struct CdfBuilder {
     version: u64,
     timestamp: String,
     expiration_timestamp: Option<String>,
+    delta_action: Option<Value>,
 }
 
 impl CdfBuilder {
@@ -551,6 +800,7 @@ impl CdfBuilder {
             version,
             timestamp: timestamp.into(),
             expiration_timestamp: None,
+            delta_action: None,
         }
     }
 
@@ -579,6 +829,12 @@ impl CdfBuilder {
         self
     }
 
+    /// Set the action of the underlying Delta table that describes this file.
+    pub fn delta_action(mut self, delta_action: Value) -> Self {
+        self.delta_action = Some(delta_action);
+        self
+    }
+
     /// Build a Cdf from the provided configuration.
     pub fn build(self) -> Cdf {
         Cdf {
@@ -590,6 +846,7 @@ impl CdfBuilder {
             version: self.version,
             timestamp: self.timestamp,
             expiration_timestamp: self.expiration_timestamp,
+            delta_action: self.delta_action,
         }
     }
 }
@@ -607,6 +864,7 @@ pub struct Remove {
     /// The size of the file in bytes.
     pub size: u64,
     /// Summary statistics about the data in this file.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub stats: Option<String>,
     /// The table version associated with this file.
     pub version: u64,
@@ -615,7 +873,15 @@ pub struct Remove {
     pub timestamp: String,
     /// The unix timestamp in milliseconds corresponding to the expiration of
     /// the url associated with this file.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub expiration_timestamp: Option<String>,
+    /// The action of the underlying Delta table that describes this file, if
+    /// the table is stored in the Delta Lake format.
+    #[serde(skip)]
+    pub delta_action: Option<Value>,
+    /// The deletion vector file that marks rows of this file as deleted.
+    #[serde(skip)]
+    pub deletion_vector: Option<DeletionVectorFile>,
 }
 
 /// Build a remove action
@@ -628,6 +894,8 @@ pub struct RemoveBuilder {
     version: Option<u64>,
     timestamp: Option<String>,
     expiration_timestamp: Option<String>,
+    delta_action: Option<Value>,
+    deletion_vector: Option<DeletionVectorFile>,
 }
 
 impl RemoveBuilder {
@@ -642,6 +910,8 @@ impl RemoveBuilder {
             version: None,
             timestamp: None,
             expiration_timestamp: None,
+            delta_action: None,
+            deletion_vector: None,
         }
     }
 
@@ -664,12 +934,36 @@ impl RemoveBuilder {
         self
     }
 
+    /// Set the table version in which this file was removed.
+    pub fn version(mut self, version: u64) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Set the timestamp of the table version in which this file was removed.
+    pub fn timestamp(mut self, ts: impl Into<String>) -> Self {
+        self.timestamp = Some(ts.into());
+        self
+    }
+
     /// Expiration timestamp for the url associated with this file.
     pub fn expiration_timestamp(mut self, ts: impl Into<String>) -> Self {
         self.expiration_timestamp = Some(ts.into());
         self
     }
 
+    /// Set the action of the underlying Delta table that describes this file.
+    pub fn delta_action(mut self, delta_action: Value) -> Self {
+        self.delta_action = Some(delta_action);
+        self
+    }
+
+    /// Set the deletion vector file that marks rows of this file as deleted.
+    pub fn deletion_vector(mut self, id: impl Into<String>, url: impl Into<String>) -> Self {
+        self.deletion_vector = Some(DeletionVectorFile::new(id, url));
+        self
+    }
+
     /// Build a Remove from the provided configuration.
     pub fn build(self) -> Remove {
         Remove {
@@ -681,6 +975,8 @@ impl RemoveBuilder {
             version: self.version.unwrap_or(0),
             timestamp: self.timestamp.unwrap_or("0".to_string()),
             expiration_timestamp: self.expiration_timestamp,
+            delta_action: self.delta_action,
+            deletion_vector: self.deletion_vector,
         }
     }
 }
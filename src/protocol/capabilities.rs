@@ -0,0 +1,387 @@
+//! Types for negotiating the capabilities of a Delta Sharing client.
+//!
+//! Clients announce the response formats and Delta reader features they
+//! support in the `delta-sharing-capabilities` header, e.g.
+//! `responseformat=delta;readerfeatures=deletionvectors,columnmapping`. The
+//! server picks the response format based on these capabilities and the
+//! protocol of the shared table, and echoes the outcome in the response.
+
+use std::{error::Error, fmt::Display};
+
+use super::action::{DeltaProtocol, Protocol};
+
+/// Name of the header used to exchange the capabilities of client and server.
+pub const CAPABILITIES_HEADER: &str = "delta-sharing-capabilities";
+
+/// Name of the reader feature of Delta tables with deletion vectors.
+pub const DELETION_VECTORS: &str = "deletionVectors";
+
+/// Format of the actions in a table query response.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResponseFormat {
+    /// Legacy format in which the data files are described by the Delta
+    /// Sharing protocol itself.
+    #[default]
+    Parquet,
+    /// Format in which the raw actions of the Delta transaction log are
+    /// wrapped in the response.
+    Delta,
+}
+
+impl ResponseFormat {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "parquet" => Some(Self::Parquet),
+            "delta" => Some(Self::Delta),
+            _ => None,
+        }
+    }
+
+    /// Retrieve the name of the response format as used in the capabilities
+    /// header.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Parquet => "parquet",
+            Self::Delta => "delta",
+        }
+    }
+}
+
+/// Capabilities announced by a client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    response_formats: Vec<ResponseFormat>,
+    reader_features: Vec<String>,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self {
+            response_formats: vec![ResponseFormat::Parquet],
+            reader_features: vec![],
+        }
+    }
+}
+
+/// Error that occurs when the client and the server cannot agree on the
+/// format of the response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CapabilitiesError {
+    /// None of the response formats accepted by the client can be used.
+    UnsupportedResponseFormat {
+        /// Response formats accepted by the client.
+        formats: Vec<String>,
+    },
+    /// The table requires reader features the client does not support.
+    UnsupportedReaderFeatures {
+        /// Reader features of the table that the client does not support.
+        features: Vec<String>,
+    },
+    /// The table has a reader feature that cannot be represented in the
+    /// parquet response format.
+    ReaderFeatureRequiresDeltaFormat {
+        /// The reader feature of the table.
+        feature: String,
+    },
+}
+
+impl Display for CapabilitiesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CapabilitiesError::UnsupportedResponseFormat { formats } => write!(
+                f,
+                "the table cannot be shared in response format `{}`",
+                formats.join(",")
+            ),
+            CapabilitiesError::UnsupportedReaderFeatures { features } => write!(
+                f,
+                "the table requires reader features that are not supported by the client: `{}`",
+                features.join(",")
+            ),
+            CapabilitiesError::ReaderFeatureRequiresDeltaFormat { feature } => write!(
+                f,
+                "the table uses reader feature `{}` and can only be shared in response format `delta`",
+                feature
+            ),
+        }
+    }
+}
+
+impl Error for CapabilitiesError {}
+
+impl Capabilities {
+    /// Parse the capabilities from the value of the capabilities header.
+    ///
+    /// Keys are case insensitive and unknown keys are ignored. When the client
+    /// does not announce a known response format, the parquet format is used.
+    ///
+    /// # Example
+    /// ```
+    /// use delta_sharing_server::protocol::capabilities::{Capabilities, ResponseFormat};
+    ///
+    /// let capabilities = Capabilities::from_header(
+    ///     "responseformat=delta;readerfeatures=deletionvectors,columnmapping",
+    /// );
+    /// assert_eq!(capabilities.response_formats(), &[ResponseFormat::Delta]);
+    /// assert!(capabilities.supports_reader_feature("deletionVectors"));
+    /// ```
+    pub fn from_header(header: &str) -> Self {
+        let mut response_formats = vec![];
+        let mut reader_features = vec![];
+        for (key, values) in header.split(';').filter_map(|kv| kv.split_once('=')) {
+            let values = values.split(',').map(str::trim).filter(|v| !v.is_empty());
+            match key.trim().to_ascii_lowercase().as_str() {
+                "responseformat" => {
+                    response_formats.extend(values.filter_map(ResponseFormat::from_name))
+                }
+                "readerfeatures" => reader_features.extend(values.map(str::to_owned)),
+                _ => {}
+            }
+        }
+
+        if response_formats.is_empty() {
+            response_formats.push(ResponseFormat::Parquet);
+        }
+        Self {
+            response_formats,
+            reader_features,
+        }
+    }
+
+    /// Retrieve the response formats accepted by the client.
+    pub fn response_formats(&self) -> &[ResponseFormat] {
+        &self.response_formats
+    }
+
+    /// Retrieve the Delta reader features supported by the client.
+    pub fn reader_features(&self) -> &[String] {
+        &self.reader_features
+    }
+
+    /// Determine whether the client accepts the response format.
+    pub fn accepts(&self, format: ResponseFormat) -> bool {
+        self.response_formats.contains(&format)
+    }
+
+    /// Determine whether the client supports a Delta reader feature. Feature
+    /// names are case insensitive.
+    pub fn supports_reader_feature(&self, feature: &str) -> bool {
+        self.reader_features
+            .iter()
+            .any(|f| f.eq_ignore_ascii_case(feature))
+    }
+
+    /// Negotiate the format of the response for a table with the given
+    /// protocol.
+    ///
+    /// The Delta format is used when the client only accepts the Delta
+    /// format, or when it accepts both formats and the table has reader
+    /// features. In the Delta format the client must support every reader
+    /// feature of the table. Tables with deletion vectors cannot be shared in
+    /// the parquet format, as the deleted rows would be returned.
+    pub fn negotiate(
+        &self,
+        protocol: &Protocol,
+    ) -> Result<ResponseCapabilities, CapabilitiesError> {
+        let table_features = protocol
+            .delta_protocol()
+            .map(DeltaProtocol::reader_features)
+            .unwrap_or_default();
+        let use_delta_format = protocol.delta_protocol().is_some()
+            && self.accepts(ResponseFormat::Delta)
+            && !(self.accepts(ResponseFormat::Parquet) && table_features.is_empty());
+
+        if !use_delta_format {
+            if !self.accepts(ResponseFormat::Parquet) {
+                return Err(CapabilitiesError::UnsupportedResponseFormat {
+                    formats: self
+                        .response_formats
+                        .iter()
+                        .map(|f| f.name().to_owned())
+                        .collect(),
+                });
+            }
+            if let Some(feature) = table_features
+                .iter()
+                .find(|f| f.eq_ignore_ascii_case(DELETION_VECTORS))
+            {
+                return Err(CapabilitiesError::ReaderFeatureRequiresDeltaFormat {
+                    feature: feature.to_string(),
+                });
+            }
+            return Ok(ResponseCapabilities::default());
+        }
+
+        let unsupported_features = table_features
+            .iter()
+            .filter(|f| !self.supports_reader_feature(f))
+            .map(|f| f.to_string())
+            .collect::<Vec<_>>();
+        if !unsupported_features.is_empty() {
+            return Err(CapabilitiesError::UnsupportedReaderFeatures {
+                features: unsupported_features,
+            });
+        }
+
+        Ok(ResponseCapabilities {
+            format: ResponseFormat::Delta,
+            reader_features: table_features.into_iter().map(String::from).collect(),
+        })
+    }
+}
+
+/// Capabilities the server used to answer a request.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResponseCapabilities {
+    format: ResponseFormat,
+    reader_features: Vec<String>,
+}
+
+impl ResponseCapabilities {
+    /// Retrieve the format of the response.
+    pub fn format(&self) -> ResponseFormat {
+        self.format
+    }
+
+    /// Retrieve the reader features of the table that the client must apply
+    /// when reading the response.
+    pub fn reader_features(&self) -> &[String] {
+        &self.reader_features
+    }
+
+    /// Retrieve the value of the capabilities header of the response.
+    pub fn header_value(&self) -> String {
+        let mut value = format!("responseformat={}", self.format.name());
+        if !self.reader_features.is_empty() {
+            value.push_str(";readerfeatures=");
+            value.push_str(&self.reader_features.join(","));
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::action::ProtocolBuilder;
+
+    fn protocol(delta_protocol: DeltaProtocol) -> Protocol {
+        ProtocolBuilder::new()
+            .delta_protocol(delta_protocol)
+            .build()
+    }
+
+    fn deletion_vectors_protocol() -> Protocol {
+        protocol(
+            DeltaProtocol::new(3, 7).with_reader_features(vec![String::from("deletionVectors")]),
+        )
+    }
+
+    #[test]
+    fn parse_capabilities_header() {
+        let capabilities = Capabilities::from_header(
+            "ResponseFormat=delta,parquet; readerfeatures=deletionvectors, columnmapping",
+        );
+        assert_eq!(
+            capabilities.response_formats(),
+            &[ResponseFormat::Delta, ResponseFormat::Parquet]
+        );
+        assert_eq!(
+            capabilities.reader_features(),
+            &[
+                String::from("deletionvectors"),
+                String::from("columnmapping")
+            ]
+        );
+        assert!(capabilities.supports_reader_feature("columnMapping"));
+        assert!(!capabilities.supports_reader_feature("timestampNtz"));
+    }
+
+    #[test]
+    fn parse_capabilities_header_defaults_to_parquet() {
+        assert_eq!(Capabilities::from_header(""), Capabilities::default());
+        assert_eq!(
+            Capabilities::from_header("responseformat=csv;foo=bar"),
+            Capabilities::default()
+        );
+    }
+
+    #[test]
+    fn negotiate_parquet_format() {
+        let caps = Capabilities::default()
+            .negotiate(&protocol(DeltaProtocol::new(2, 5)))
+            .unwrap();
+        assert_eq!(caps.format(), ResponseFormat::Parquet);
+        assert_eq!(caps.header_value(), "responseformat=parquet");
+
+        let caps = Capabilities::from_header("responseformat=delta,parquet")
+            .negotiate(&protocol(DeltaProtocol::new(1, 2)))
+            .unwrap();
+        assert_eq!(caps.format(), ResponseFormat::Parquet);
+    }
+
+    #[test]
+    fn negotiate_delta_format() {
+        let caps = Capabilities::from_header("responseformat=delta")
+            .negotiate(&protocol(DeltaProtocol::new(1, 2)))
+            .unwrap();
+        assert_eq!(caps.format(), ResponseFormat::Delta);
+        assert_eq!(caps.header_value(), "responseformat=delta");
+
+        let caps = Capabilities::from_header(
+            "responseformat=delta,parquet;readerfeatures=deletionvectors",
+        )
+        .negotiate(&deletion_vectors_protocol())
+        .unwrap();
+        assert_eq!(caps.format(), ResponseFormat::Delta);
+        assert_eq!(
+            caps.header_value(),
+            "responseformat=delta;readerfeatures=deletionVectors"
+        );
+    }
+
+    #[test]
+    fn negotiate_unsupported_reader_features() {
+        let err = Capabilities::from_header("responseformat=delta;readerfeatures=columnmapping")
+            .negotiate(&deletion_vectors_protocol())
+            .unwrap_err();
+        assert_eq!(
+            err,
+            CapabilitiesError::UnsupportedReaderFeatures {
+                features: vec![String::from("deletionVectors")]
+            }
+        );
+    }
+
+    #[test]
+    fn negotiate_deletion_vectors_in_parquet_format() {
+        let err = Capabilities::default()
+            .negotiate(&deletion_vectors_protocol())
+            .unwrap_err();
+        assert_eq!(
+            err,
+            CapabilitiesError::ReaderFeatureRequiresDeltaFormat {
+                feature: String::from("deletionVectors")
+            }
+        );
+    }
+
+    #[test]
+    fn negotiate_without_delta_protocol() {
+        let table_protocol = ProtocolBuilder::new().build();
+        let caps = Capabilities::from_header("responseformat=delta,parquet")
+            .negotiate(&table_protocol)
+            .unwrap();
+        assert_eq!(caps.format(), ResponseFormat::Parquet);
+
+        let err = Capabilities::from_header("responseformat=delta")
+            .negotiate(&table_protocol)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            CapabilitiesError::UnsupportedResponseFormat {
+                formats: vec![String::from("delta")]
+            }
+        );
+    }
+}
@@ -1,6 +1,8 @@
 //! Types for implementing the Delta Sharing protocol.
 
 pub mod action;
+pub mod capabilities;
+pub mod predicate;
 pub mod securable;
 pub mod share;
 pub mod table;
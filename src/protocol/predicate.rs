@@ -0,0 +1,888 @@
+//! Types for the JSON predicate language used to filter table data.
+//!
+//! Recipients can send a predicate along with a table data query. The server
+//! uses the predicate on a best effort basis to skip data files that cannot
+//! contain matching rows, based on the partition values and the statistics of
+//! the data files. The recipient is still responsible for filtering the rows
+//! of the returned data files.
+
+use std::{cmp::Ordering, collections::HashMap, error::Error, fmt::Display};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// Boolean expression that a row of the table must satisfy.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    /// The operand is null.
+    IsNull(Operand),
+    /// The operands are equal.
+    Equal(Operand, Operand),
+    /// The left operand is less than the right operand.
+    LessThan(Operand, Operand),
+    /// The left operand is less than or equal to the right operand.
+    LessThanOrEqual(Operand, Operand),
+    /// The left operand is greater than the right operand.
+    GreaterThan(Operand, Operand),
+    /// The left operand is greater than or equal to the right operand.
+    GreaterThanOrEqual(Operand, Operand),
+    /// All child predicates hold.
+    And(Vec<Predicate>),
+    /// At least one child predicate holds.
+    Or(Vec<Predicate>),
+    /// The child predicate does not hold.
+    Not(Box<Predicate>),
+}
+
+/// Value that is compared in a predicate.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    /// Reference to a column of the table.
+    Column {
+        /// Name of the column. Nested columns are separated by a dot.
+        name: String,
+        /// Type of the values in the column.
+        value_type: ValueType,
+    },
+    /// Constant value.
+    Literal {
+        /// The constant value in its string representation.
+        value: String,
+        /// Type of the constant value.
+        value_type: ValueType,
+    },
+}
+
+/// Type of the values in a predicate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    /// Boolean value
+    Bool,
+    /// 32-bit signed integer
+    Int,
+    /// 64-bit signed integer
+    Long,
+    /// UTF-8 string
+    String,
+    /// Calendar date
+    Date,
+    /// 32-bit floating point number
+    Float,
+    /// 64-bit floating point number
+    Double,
+    /// Timestamp in UTC
+    Timestamp,
+}
+
+impl ValueType {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bool" | "boolean" => Some(ValueType::Bool),
+            "int" => Some(ValueType::Int),
+            "long" => Some(ValueType::Long),
+            "string" => Some(ValueType::String),
+            "date" => Some(ValueType::Date),
+            "float" => Some(ValueType::Float),
+            "double" => Some(ValueType::Double),
+            "timestamp" => Some(ValueType::Timestamp),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ValueType::Bool => "boolean",
+            ValueType::Int => "int",
+            ValueType::Long => "long",
+            ValueType::String => "string",
+            ValueType::Date => "date",
+            ValueType::Float => "float",
+            ValueType::Double => "double",
+            ValueType::Timestamp => "timestamp",
+        }
+    }
+}
+
+impl Operand {
+    fn to_value(&self) -> Value {
+        match self {
+            Operand::Column { name, value_type } => {
+                json!({ "op": "column", "name": name, "valueType": value_type.name() })
+            }
+            Operand::Literal { value, value_type } => {
+                json!({ "op": "literal", "value": value, "valueType": value_type.name() })
+            }
+        }
+    }
+}
+
+/// Errors that can occur while parsing a predicate.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PredicateError {
+    /// The predicate is not valid JSON.
+    InvalidJson {
+        /// Reason why the JSON could not be parsed.
+        reason: String,
+    },
+    /// The predicate is not a valid expression.
+    InvalidExpression {
+        /// Reason why the expression is invalid.
+        reason: String,
+    },
+}
+
+impl Display for PredicateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PredicateError::InvalidJson { reason } => {
+                write!(f, "the predicate is not valid JSON: {}", reason)
+            }
+            PredicateError::InvalidExpression { reason } => {
+                write!(f, "the predicate is not a valid expression: {}", reason)
+            }
+        }
+    }
+}
+
+impl Error for PredicateError {}
+
+impl Predicate {
+    /// Parse a predicate from the JSON predicate language of the Delta
+    /// Sharing protocol.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use delta_sharing_server::protocol::predicate::{Operand, Predicate, ValueType};
+    ///
+    /// let json = r#"{
+    ///     "op": "equal",
+    ///     "children": [
+    ///         {"op": "column", "name": "date", "valueType": "date"},
+    ///         {"op": "literal", "value": "2023-01-01", "valueType": "date"}
+    ///     ]
+    /// }"#;
+    /// let predicate = Predicate::from_json(json).unwrap();
+    /// assert_eq!(
+    ///     predicate,
+    ///     Predicate::Equal(
+    ///         Operand::Column { name: String::from("date"), value_type: ValueType::Date },
+    ///         Operand::Literal { value: String::from("2023-01-01"), value_type: ValueType::Date },
+    ///     )
+    /// );
+    /// ```
+    pub fn from_json(json: &str) -> Result<Self, PredicateError> {
+        let expression: JsonExpression =
+            serde_json::from_str(json).map_err(|e| PredicateError::InvalidJson {
+                reason: e.to_string(),
+            })?;
+        expression.into_predicate()
+    }
+
+    /// Serialize the predicate in the JSON predicate language of the Delta
+    /// Sharing protocol. Parsing the result yields the same predicate.
+    pub fn to_json(&self) -> String {
+        self.to_value().to_string()
+    }
+
+    fn to_value(&self) -> Value {
+        let (op, children) = match self {
+            Predicate::IsNull(operand) => ("isNull", vec![operand.to_value()]),
+            Predicate::Equal(left, right) => ("equal", vec![left.to_value(), right.to_value()]),
+            Predicate::LessThan(left, right) => {
+                ("lessThan", vec![left.to_value(), right.to_value()])
+            }
+            Predicate::LessThanOrEqual(left, right) => {
+                ("lessThanOrEqual", vec![left.to_value(), right.to_value()])
+            }
+            Predicate::GreaterThan(left, right) => {
+                ("greaterThan", vec![left.to_value(), right.to_value()])
+            }
+            Predicate::GreaterThanOrEqual(left, right) => (
+                "greaterThanOrEqual",
+                vec![left.to_value(), right.to_value()],
+            ),
+            Predicate::And(children) => ("and", children.iter().map(Self::to_value).collect()),
+            Predicate::Or(children) => ("or", children.iter().map(Self::to_value).collect()),
+            Predicate::Not(child) => ("not", vec![child.to_value()]),
+        };
+        json!({ "op": op, "children": children })
+    }
+
+    /// Determine whether a data file may contain rows that satisfy the
+    /// predicate, given the partition values and JSON statistics of the file.
+    ///
+    /// Returns `false` only when the file certainly contains no matching
+    /// rows. Columns without partition value or statistics, and values that
+    /// cannot be interpreted, never cause a file to be skipped.
+    pub fn may_match(
+        &self,
+        partition_values: &HashMap<String, Option<String>>,
+        stats: Option<&str>,
+    ) -> bool {
+        let stats = stats.and_then(|stats| serde_json::from_str::<Value>(stats).ok());
+        let file = FileValues {
+            partition_values,
+            stats: stats.as_ref(),
+        };
+        self.evaluate(&file, false)
+    }
+
+    /// Evaluate whether the predicate may hold for some row in the file. When
+    /// `negated` is set the negation of the predicate is evaluated instead.
+    fn evaluate(&self, file: &FileValues, negated: bool) -> bool {
+        match self {
+            Predicate::IsNull(operand) => match file.bounds(operand) {
+                Some(bounds) if negated => bounds.may_be_non_null(),
+                Some(bounds) => bounds.may_be_null(),
+                None => true,
+            },
+            Predicate::Equal(left, right) => {
+                let comparison = if negated {
+                    Comparison::NotEqual
+                } else {
+                    Comparison::Equal
+                };
+                file.compare(left, comparison, right)
+            }
+            Predicate::LessThan(left, right) => {
+                let comparison = if negated {
+                    Comparison::GreaterThanOrEqual
+                } else {
+                    Comparison::LessThan
+                };
+                file.compare(left, comparison, right)
+            }
+            Predicate::LessThanOrEqual(left, right) => {
+                let comparison = if negated {
+                    Comparison::GreaterThan
+                } else {
+                    Comparison::LessThanOrEqual
+                };
+                file.compare(left, comparison, right)
+            }
+            Predicate::GreaterThan(left, right) => {
+                let comparison = if negated {
+                    Comparison::LessThanOrEqual
+                } else {
+                    Comparison::GreaterThan
+                };
+                file.compare(left, comparison, right)
+            }
+            Predicate::GreaterThanOrEqual(left, right) => {
+                let comparison = if negated {
+                    Comparison::LessThan
+                } else {
+                    Comparison::GreaterThanOrEqual
+                };
+                file.compare(left, comparison, right)
+            }
+            // not(a and b) is equivalent to not(a) or not(b)
+            Predicate::And(children) if negated => children.iter().any(|c| c.evaluate(file, true)),
+            Predicate::And(children) => children.iter().all(|c| c.evaluate(file, false)),
+            // not(a or b) is equivalent to not(a) and not(b)
+            Predicate::Or(children) if negated => children.iter().all(|c| c.evaluate(file, true)),
+            Predicate::Or(children) => children.iter().any(|c| c.evaluate(file, false)),
+            Predicate::Not(child) => child.evaluate(file, !negated),
+        }
+    }
+}
+
+/// Raw representation of an expression in the JSON predicate language.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonExpression {
+    op: String,
+    #[serde(default)]
+    children: Vec<JsonExpression>,
+    name: Option<String>,
+    value: Option<String>,
+    value_type: Option<String>,
+}
+
+impl JsonExpression {
+    fn into_predicate(self) -> Result<Predicate, PredicateError> {
+        let JsonExpression { op, children, .. } = self;
+        match op.as_str() {
+            "isNull" => {
+                let [operand] = operands::<1>(&op, children)?;
+                Ok(Predicate::IsNull(operand))
+            }
+            "equal" => {
+                let [left, right] = operands::<2>(&op, children)?;
+                Ok(Predicate::Equal(left, right))
+            }
+            "lessThan" => {
+                let [left, right] = operands::<2>(&op, children)?;
+                Ok(Predicate::LessThan(left, right))
+            }
+            "lessThanOrEqual" => {
+                let [left, right] = operands::<2>(&op, children)?;
+                Ok(Predicate::LessThanOrEqual(left, right))
+            }
+            "greaterThan" => {
+                let [left, right] = operands::<2>(&op, children)?;
+                Ok(Predicate::GreaterThan(left, right))
+            }
+            "greaterThanOrEqual" => {
+                let [left, right] = operands::<2>(&op, children)?;
+                Ok(Predicate::GreaterThanOrEqual(left, right))
+            }
+            "and" | "or" => {
+                if children.len() < 2 {
+                    return Err(invalid_expression(format!(
+                        "`{}` requires at least two children",
+                        op
+                    )));
+                }
+                let children = children
+                    .into_iter()
+                    .map(JsonExpression::into_predicate)
+                    .collect::<Result<Vec<_>, _>>()?;
+                if op == "and" {
+                    Ok(Predicate::And(children))
+                } else {
+                    Ok(Predicate::Or(children))
+                }
+            }
+            "not" => match <[JsonExpression; 1]>::try_from(children) {
+                Ok([child]) => Ok(Predicate::Not(Box::new(child.into_predicate()?))),
+                Err(_) => Err(invalid_expression("`not` requires exactly one child")),
+            },
+            _ => Err(invalid_expression(format!(
+                "`{}` is not a supported boolean operation",
+                op
+            ))),
+        }
+    }
+
+    fn into_operand(self) -> Result<Operand, PredicateError> {
+        let value_type = match self.value_type.as_deref() {
+            Some(name) => ValueType::from_name(name)
+                .ok_or_else(|| invalid_expression(format!("`{}` is not a valid type", name)))?,
+            None => return Err(invalid_expression(format!("`{}` requires a type", self.op))),
+        };
+        match (self.op.as_str(), self.name, self.value) {
+            ("column", Some(name), _) => Ok(Operand::Column { name, value_type }),
+            ("literal", _, Some(value)) => Ok(Operand::Literal { value, value_type }),
+            ("column", None, _) => Err(invalid_expression("`column` requires a name")),
+            ("literal", _, None) => Err(invalid_expression("`literal` requires a value")),
+            (op, _, _) => Err(invalid_expression(format!(
+                "`{}` is not a supported leaf operation",
+                op
+            ))),
+        }
+    }
+}
+
+fn operands<const N: usize>(
+    op: &str,
+    children: Vec<JsonExpression>,
+) -> Result<[Operand; N], PredicateError> {
+    let children = <[JsonExpression; N]>::try_from(children)
+        .map_err(|_| invalid_expression(format!("`{}` requires exactly {} children", op, N)))?;
+    let mut operands = Vec::with_capacity(N);
+    for child in children {
+        operands.push(child.into_operand()?);
+    }
+    Ok(operands
+        .try_into()
+        .expect("every child is converted into an operand"))
+}
+
+fn invalid_expression(reason: impl Into<String>) -> PredicateError {
+    PredicateError::InvalidExpression {
+        reason: reason.into(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+}
+
+impl Comparison {
+    /// The comparison that holds when the operands are swapped.
+    fn flip(self) -> Self {
+        match self {
+            Comparison::LessThan => Comparison::GreaterThan,
+            Comparison::LessThanOrEqual => Comparison::GreaterThanOrEqual,
+            Comparison::GreaterThan => Comparison::LessThan,
+            Comparison::GreaterThanOrEqual => Comparison::LessThanOrEqual,
+            comparison => comparison,
+        }
+    }
+}
+
+/// Typed value used for comparisons.
+#[derive(Debug, Clone, PartialEq)]
+enum TypedValue {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Date(NaiveDate),
+    Timestamp(DateTime<Utc>),
+}
+
+impl TypedValue {
+    fn parse(value: &str, value_type: ValueType) -> Option<Self> {
+        match value_type {
+            ValueType::Bool => value.parse().ok().map(TypedValue::Bool),
+            ValueType::Int | ValueType::Long => value.parse().ok().map(TypedValue::Integer),
+            ValueType::Float | ValueType::Double => value.parse().ok().map(TypedValue::Float),
+            ValueType::String => Some(TypedValue::String(value.to_owned())),
+            ValueType::Date => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .map(TypedValue::Date),
+            ValueType::Timestamp => parse_timestamp(value).map(TypedValue::Timestamp),
+        }
+    }
+
+    fn from_json(value: &Value, value_type: ValueType) -> Option<Self> {
+        match value {
+            Value::String(s) => Self::parse(s, value_type),
+            Value::Number(_) | Value::Bool(_) => Self::parse(&value.to_string(), value_type),
+            _ => None,
+        }
+    }
+}
+
+impl PartialOrd for TypedValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (TypedValue::Bool(a), TypedValue::Bool(b)) => a.partial_cmp(b),
+            (TypedValue::Integer(a), TypedValue::Integer(b)) => a.partial_cmp(b),
+            (TypedValue::Float(a), TypedValue::Float(b)) => a.partial_cmp(b),
+            (TypedValue::String(a), TypedValue::String(b)) => a.partial_cmp(b),
+            (TypedValue::Date(a), TypedValue::Date(b)) => a.partial_cmp(b),
+            (TypedValue::Timestamp(a), TypedValue::Timestamp(b)) => a.partial_cmp(b),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a timestamp as written in statistics (RFC 3339) or in partition
+/// values (`yyyy-MM-dd HH:mm:ss[.SSS]`, interpreted as UTC).
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Some(ts.with_timezone(&Utc));
+    }
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|ts| Utc.from_utc_datetime(&ts))
+}
+
+/// The range of values that a column takes within a data file. Unknown
+/// bounds are represented by `None`.
+#[derive(Debug, Clone)]
+struct Bounds {
+    min: Option<TypedValue>,
+    max: Option<TypedValue>,
+    null_count: Option<i64>,
+    num_records: Option<i64>,
+}
+
+impl Bounds {
+    fn exact(value: Option<TypedValue>) -> Self {
+        let is_null = value.is_none();
+        Self {
+            min: value.clone(),
+            max: value,
+            null_count: Some(if is_null { 1 } else { 0 }),
+            num_records: Some(1),
+        }
+    }
+
+    fn may_be_null(&self) -> bool {
+        self.null_count != Some(0)
+    }
+
+    fn may_be_non_null(&self) -> bool {
+        match (self.null_count, self.num_records) {
+            (Some(null_count), Some(num_records)) => null_count < num_records,
+            _ => true,
+        }
+    }
+
+    /// Determine whether a non-null value in the bounds may satisfy the
+    /// comparison with the literal value.
+    fn may_compare(&self, comparison: Comparison, literal: &TypedValue) -> bool {
+        if !self.may_be_non_null() {
+            return false;
+        }
+        let min = self.min.as_ref().and_then(|min| min.partial_cmp(literal));
+        let max = self.max.as_ref().and_then(|max| max.partial_cmp(literal));
+        match comparison {
+            Comparison::Equal => min != Some(Ordering::Greater) && max != Some(Ordering::Less),
+            Comparison::NotEqual => !(min == Some(Ordering::Equal) && max == Some(Ordering::Equal)),
+            Comparison::LessThan => !matches!(min, Some(Ordering::Greater | Ordering::Equal)),
+            Comparison::LessThanOrEqual => min != Some(Ordering::Greater),
+            Comparison::GreaterThan => !matches!(max, Some(Ordering::Less | Ordering::Equal)),
+            Comparison::GreaterThanOrEqual => max != Some(Ordering::Less),
+        }
+    }
+}
+
+/// Partition values and statistics of a single data file.
+struct FileValues<'a> {
+    partition_values: &'a HashMap<String, Option<String>>,
+    stats: Option<&'a Value>,
+}
+
+impl FileValues<'_> {
+    /// Determine the bounds of a column in the file. Returns `None` when the
+    /// operand is not a column or nothing is known about the column.
+    fn bounds(&self, operand: &Operand) -> Option<Bounds> {
+        let (name, value_type) = match operand {
+            Operand::Column { name, value_type } => (name, *value_type),
+            Operand::Literal { .. } => return None,
+        };
+
+        if let Some(value) = self.partition_value(name) {
+            return match value {
+                Some(value) => TypedValue::parse(value, value_type).map(|v| Bounds::exact(Some(v))),
+                None => Some(Bounds::exact(None)),
+            };
+        }
+
+        let stats = self.stats?;
+        Some(Bounds {
+            min: column_stat(stats, "minValues", name)
+                .and_then(|v| TypedValue::from_json(v, value_type)),
+            max: column_stat(stats, "maxValues", name)
+                .and_then(|v| TypedValue::from_json(v, value_type))
+                .and_then(max_bound),
+            null_count: column_stat(stats, "nullCount", name).and_then(Value::as_i64),
+            num_records: stats.get("numRecords").and_then(Value::as_i64),
+        })
+    }
+
+    fn partition_value(&self, name: &str) -> Option<&Option<String>> {
+        self.partition_values.get(name).or_else(|| {
+            self.partition_values
+                .iter()
+                .find(|(column, _)| column.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value)
+        })
+    }
+
+    /// Determine whether some row in the file may satisfy the comparison.
+    /// Only comparisons between a column and a literal value can rule out
+    /// a file.
+    fn compare(&self, left: &Operand, comparison: Comparison, right: &Operand) -> bool {
+        let (column, comparison, literal) = match (left, right) {
+            (Operand::Column { .. }, Operand::Literal { value, value_type }) => {
+                (left, comparison, (value, *value_type))
+            }
+            (Operand::Literal { value, value_type }, Operand::Column { .. }) => {
+                (right, comparison.flip(), (value, *value_type))
+            }
+            _ => return true,
+        };
+        let literal = match TypedValue::parse(literal.0, literal.1) {
+            Some(literal) => literal,
+            None => return true,
+        };
+        match self.bounds(column) {
+            Some(bounds) => bounds.may_compare(comparison, &literal),
+            None => true,
+        }
+    }
+}
+
+/// Turn the maximum value in the statistics of a file into an upper bound of
+/// the values in the file.
+///
+/// Writers truncate timestamp statistics to milliseconds and may truncate
+/// the maximum of strings, so the recorded maximum can be smaller than the
+/// actual maximum. Timestamps are widened by a millisecond and string
+/// maxima are not used at all.
+fn max_bound(max: TypedValue) -> Option<TypedValue> {
+    match max {
+        TypedValue::Timestamp(ts) => Some(TypedValue::Timestamp(
+            ts + chrono::Duration::milliseconds(1),
+        )),
+        TypedValue::String(_) => None,
+        max => Some(max),
+    }
+}
+
+/// Look up the statistic of a (nested) column in the statistics of a file.
+/// As for partition values, column names are case insensitive.
+fn column_stat<'v>(stats: &'v Value, kind: &str, name: &str) -> Option<&'v Value> {
+    name.split('.').try_fold(stats.get(kind)?, |value, field| {
+        value.get(field).or_else(|| {
+            value
+                .as_object()?
+                .iter()
+                .find(|(column, _)| column.eq_ignore_ascii_case(field))
+                .map(|(_, value)| value)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, value_type: ValueType) -> Operand {
+        Operand::Column {
+            name: name.to_owned(),
+            value_type,
+        }
+    }
+
+    fn literal(value: &str, value_type: ValueType) -> Operand {
+        Operand::Literal {
+            value: value.to_owned(),
+            value_type,
+        }
+    }
+
+    fn partitions(date: Option<&str>) -> HashMap<String, Option<String>> {
+        HashMap::from([(String::from("date"), date.map(String::from))])
+    }
+
+    const STATS: &str = r#"{
+        "numRecords": 10,
+        "minValues": {"id": 5, "name": "bar", "event": {"ts": "2023-01-01T10:00:00.000Z"}},
+        "maxValues": {"id": 15, "name": "foo", "event": {"ts": "2023-01-01T12:00:00.000Z"}},
+        "nullCount": {"id": 0, "name": 10, "event": {"ts": 2}}
+    }"#;
+
+    #[test]
+    fn parse_predicate() {
+        let json = r#"{
+            "op": "and",
+            "children": [
+                {"op": "not", "children": [
+                    {"op": "isNull", "children": [{"op": "column", "name": "id", "valueType": "int"}]}
+                ]},
+                {"op": "greaterThanOrEqual", "children": [
+                    {"op": "column", "name": "date", "valueType": "date"},
+                    {"op": "literal", "value": "2023-01-01", "valueType": "date"}
+                ]}
+            ]
+        }"#;
+        assert_eq!(
+            Predicate::from_json(json).unwrap(),
+            Predicate::And(vec![
+                Predicate::Not(Box::new(Predicate::IsNull(column("id", ValueType::Int)))),
+                Predicate::GreaterThanOrEqual(
+                    column("date", ValueType::Date),
+                    literal("2023-01-01", ValueType::Date)
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn serialize_predicate() {
+        let predicate = Predicate::Or(vec![
+            Predicate::Not(Box::new(Predicate::IsNull(column("id", ValueType::Long)))),
+            Predicate::And(vec![
+                Predicate::LessThan(
+                    column("price", ValueType::Double),
+                    literal("9.5", ValueType::Double),
+                ),
+                Predicate::Equal(
+                    column("active", ValueType::Bool),
+                    literal("true", ValueType::Bool),
+                ),
+            ]),
+        ]);
+        assert_eq!(
+            Predicate::from_json(&predicate.to_json()).unwrap(),
+            predicate
+        );
+    }
+
+    #[test]
+    fn reject_invalid_predicate() {
+        for json in [
+            r#"{"op": "equal""#,
+            r#"{"op": "equal", "children": [{"op": "column", "name": "id", "valueType": "int"}]}"#,
+            r#"{"op": "and", "children": [{"op": "literal", "value": "true", "valueType": "bool"}]}"#,
+            r#"{"op": "isNull", "children": [{"op": "column", "name": "id", "valueType": "uuid"}]}"#,
+            r#"{"op": "isNull", "children": [{"op": "column", "valueType": "int"}]}"#,
+            r#"{"op": "like", "children": []}"#,
+        ] {
+            assert!(Predicate::from_json(json).is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn prune_on_partition_values() {
+        let predicate = Predicate::Equal(
+            column("date", ValueType::Date),
+            literal("2023-01-01", ValueType::Date),
+        );
+        assert!(predicate.may_match(&partitions(Some("2023-01-01")), None));
+        assert!(!predicate.may_match(&partitions(Some("2023-01-02")), None));
+        assert!(!predicate.may_match(&partitions(None), None));
+
+        let predicate = Predicate::LessThan(
+            literal("2023-01-01", ValueType::Date),
+            column("date", ValueType::Date),
+        );
+        assert!(!predicate.may_match(&partitions(Some("2023-01-01")), None));
+        assert!(predicate.may_match(&partitions(Some("2023-01-02")), None));
+
+        let predicate = Predicate::IsNull(column("date", ValueType::Date));
+        assert!(predicate.may_match(&partitions(None), None));
+        assert!(!predicate.may_match(&partitions(Some("2023-01-01")), None));
+    }
+
+    #[test]
+    fn prune_on_stats() {
+        let no_partitions = HashMap::new();
+        let may_match = |predicate: Predicate| predicate.may_match(&no_partitions, Some(STATS));
+
+        assert!(may_match(Predicate::Equal(
+            column("id", ValueType::Int),
+            literal("10", ValueType::Int)
+        )));
+        assert!(!may_match(Predicate::Equal(
+            column("id", ValueType::Int),
+            literal("16", ValueType::Int)
+        )));
+        assert!(!may_match(Predicate::LessThan(
+            column("id", ValueType::Int),
+            literal("5", ValueType::Int)
+        )));
+        assert!(may_match(Predicate::LessThanOrEqual(
+            column("id", ValueType::Int),
+            literal("5", ValueType::Int)
+        )));
+        assert!(!may_match(Predicate::GreaterThan(
+            column("id", ValueType::Int),
+            literal("15", ValueType::Int)
+        )));
+        assert!(!may_match(Predicate::IsNull(column("id", ValueType::Int))));
+        // all values of `name` are null
+        assert!(!may_match(Predicate::Equal(
+            column("name", ValueType::String),
+            literal("foo", ValueType::String)
+        )));
+        assert!(!may_match(Predicate::GreaterThanOrEqual(
+            column("event.ts", ValueType::Timestamp),
+            literal("2023-01-01 12:00:01", ValueType::Timestamp)
+        )));
+        assert!(may_match(Predicate::GreaterThanOrEqual(
+            column("event.ts", ValueType::Timestamp),
+            literal("2023-01-01 12:00:00", ValueType::Timestamp)
+        )));
+    }
+
+    #[test]
+    fn keep_files_with_truncated_stats() {
+        let no_partitions = HashMap::new();
+        let may_match = |predicate: Predicate| predicate.may_match(&no_partitions, Some(STATS));
+
+        // A file with `2023-01-01 12:00:00.000500` records a maximum of
+        // `2023-01-01T12:00:00.000Z`.
+        for predicate in [
+            Predicate::Equal(
+                column("event.ts", ValueType::Timestamp),
+                literal("2023-01-01 12:00:00.000500", ValueType::Timestamp),
+            ),
+            Predicate::GreaterThan(
+                column("event.ts", ValueType::Timestamp),
+                literal("2023-01-01 12:00:00", ValueType::Timestamp),
+            ),
+        ] {
+            assert!(may_match(predicate));
+        }
+        assert!(!may_match(Predicate::GreaterThan(
+            column("event.ts", ValueType::Timestamp),
+            literal("2023-01-01 12:00:00.001", ValueType::Timestamp)
+        )));
+
+        // The maximum of a string may have been truncated.
+        let stats = r#"{
+            "numRecords": 10,
+            "minValues": {"name": "bar"},
+            "maxValues": {"name": "foo"},
+            "nullCount": {"name": 0}
+        }"#;
+        assert!(Predicate::GreaterThan(
+            column("name", ValueType::String),
+            literal("foobar", ValueType::String)
+        )
+        .may_match(&no_partitions, Some(stats)));
+        assert!(!Predicate::LessThan(
+            column("name", ValueType::String),
+            literal("bar", ValueType::String)
+        )
+        .may_match(&no_partitions, Some(stats)));
+    }
+
+    #[test]
+    fn stats_columns_are_case_insensitive() {
+        let no_partitions = HashMap::new();
+        let predicate =
+            Predicate::Equal(column("ID", ValueType::Int), literal("16", ValueType::Int));
+        assert!(!predicate.may_match(&no_partitions, Some(STATS)));
+        let predicate = Predicate::IsNull(column("Event.TS", ValueType::Timestamp));
+        assert!(predicate.may_match(&no_partitions, Some(STATS)));
+        let predicate = Predicate::IsNull(column("ID", ValueType::Int));
+        assert!(!predicate.may_match(&no_partitions, Some(STATS)));
+    }
+
+    #[test]
+    fn prune_with_boolean_operators() {
+        let no_partitions = HashMap::new();
+        let may_match = |predicate: Predicate| predicate.may_match(&no_partitions, Some(STATS));
+        let id_equals = |value: &str| {
+            Predicate::Equal(column("id", ValueType::Int), literal(value, ValueType::Int))
+        };
+
+        assert!(!may_match(Predicate::And(vec![
+            id_equals("10"),
+            id_equals("20")
+        ])));
+        assert!(may_match(Predicate::Or(vec![
+            id_equals("10"),
+            id_equals("20")
+        ])));
+        assert!(!may_match(Predicate::Or(vec![
+            id_equals("0"),
+            id_equals("20")
+        ])));
+        assert!(may_match(Predicate::Not(Box::new(id_equals("10")))));
+        // not(id < 20) can only hold for id >= 20
+        assert!(!may_match(Predicate::Not(Box::new(Predicate::LessThan(
+            column("id", ValueType::Int),
+            literal("20", ValueType::Int)
+        )))));
+        // not(not(id = 20)) is equivalent to id = 20
+        assert!(!may_match(Predicate::Not(Box::new(Predicate::Not(
+            Box::new(id_equals("20"))
+        )))));
+    }
+
+    #[test]
+    fn keep_files_without_information() {
+        let predicate = Predicate::Equal(
+            column("unknown", ValueType::Int),
+            literal("1", ValueType::Int),
+        );
+        assert!(predicate.may_match(&HashMap::new(), None));
+        assert!(predicate.may_match(&HashMap::new(), Some(STATS)));
+        assert!(predicate.may_match(&HashMap::new(), Some("not json")));
+
+        let predicate = Predicate::Equal(
+            column("id", ValueType::Int),
+            literal("not a number", ValueType::Int),
+        );
+        assert!(predicate.may_match(&HashMap::new(), Some(STATS)));
+    }
+}
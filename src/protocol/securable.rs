@@ -13,6 +13,8 @@ use serde::{Deserialize, Serialize};
 pub struct Share {
     name: String,
     id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
 }
 
 impl Share {
@@ -43,12 +45,27 @@ impl Share {
     pub fn id(&self) -> Option<&str> {
         self.id.as_deref()
     }
+
+    /// Retrieve the comment from `self`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use delta_sharing_server::protocol::securable::ShareBuilder;
+    ///
+    /// let share = ShareBuilder::new("my-share").comment("shared with partners").build();
+    /// assert_eq!(share.comment(), Some("shared with partners"));
+    /// ```
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
 }
 
 /// Builder for [`Share`].
 pub struct ShareBuilder {
     name: String,
     id: Option<String>,
+    comment: Option<String>,
 }
 
 impl ShareBuilder {
@@ -57,6 +74,7 @@ impl ShareBuilder {
         Self {
             name: name.into(),
             id: None,
+            comment: None,
         }
     }
 
@@ -72,11 +90,24 @@ impl ShareBuilder {
         self
     }
 
+    /// Set the comment of the share.
+    pub fn comment<S: Into<String>>(mut self, comment: S) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Set the comment of the share.
+    pub fn set_comment<S: Into<String>>(mut self, comment: Option<S>) -> Self {
+        self.comment = comment.map(Into::into);
+        self
+    }
+
     /// Build the [`Share`].
     pub fn build(self) -> Share {
         Share {
             name: self.name,
             id: self.id,
+            comment: self.comment,
         }
     }
 }
@@ -96,6 +127,8 @@ pub struct Schema {
     share: Share,
     name: String,
     id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
 }
 
 impl Schema {
@@ -158,6 +191,21 @@ impl Schema {
     pub fn id(&self) -> Option<&str> {
         self.id.as_deref()
     }
+
+    /// Returns the comment of `self`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use delta_sharing_server::protocol::securable::{ShareBuilder, SchemaBuilder};
+    ///
+    /// let share = ShareBuilder::new("my-share").build();
+    /// let schema = SchemaBuilder::new(share, "my-schema").comment("sales data").build();
+    /// assert_eq!(schema.comment(), Some("sales data"));
+    /// ```
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
 }
 
 /// Builder for [`Schema`].
@@ -165,6 +213,7 @@ pub struct SchemaBuilder {
     share: Share,
     name: String,
     id: Option<String>,
+    comment: Option<String>,
 }
 
 impl SchemaBuilder {
@@ -174,6 +223,7 @@ impl SchemaBuilder {
             share,
             name: schema_name.into(),
             id: None,
+            comment: None,
         }
     }
 
@@ -189,12 +239,25 @@ impl SchemaBuilder {
         self
     }
 
+    /// Set the comment of the schema.
+    pub fn comment<S: Into<String>>(mut self, comment: S) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Set the comment of the schema.
+    pub fn set_comment<S: Into<String>>(mut self, comment: Option<S>) -> Self {
+        self.comment = comment.map(Into::into);
+        self
+    }
+
     /// Build the [`Schema`].
     pub fn build(self) -> Schema {
         Schema {
             share: self.share,
             name: self.name,
             id: self.id,
+            comment: self.comment,
         }
     }
 }
@@ -209,6 +272,10 @@ impl Display for Schema {
 ///
 /// A table is a Delta Lake table or a view on top of a Delta Lake table. A
 /// table is defined within the context of a [`Schema`].
+///
+/// Unless configured otherwise, the full history of a table is shared, such
+/// that recipients can query earlier table versions and the changes between
+/// table versions.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Hash)]
 pub struct Table {
     schema: Schema,
@@ -216,6 +283,16 @@ pub struct Table {
     id: Option<String>,
     storage_path: String,
     format: String,
+    #[serde(default = "history_shared_by_default")]
+    history_shared: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_version: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+fn history_shared_by_default() -> bool {
+    true
 }
 
 impl Table {
@@ -351,6 +428,59 @@ impl Table {
     pub fn format(&self) -> &str {
         self.format.as_ref()
     }
+
+    /// Returns whether the history of `self` is shared
+    ///
+    /// Recipients can only query the latest version of a table of which the
+    /// history is not shared.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use delta_sharing_server::protocol::securable::{ShareBuilder, SchemaBuilder, TableBuilder};
+    ///
+    /// let share = ShareBuilder::new("my-share").build();
+    /// let schema = SchemaBuilder::new(share, "my-schema").build();
+    /// let table = TableBuilder::new(schema, "my-table", "my-storage-path").history_shared(false).build();
+    /// assert!(!table.history_shared());
+    /// ```
+    pub fn history_shared(&self) -> bool {
+        self.history_shared
+    }
+
+    /// Returns the first shared version of `self`
+    ///
+    /// Recipients cannot query the table versions before this version.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use delta_sharing_server::protocol::securable::{ShareBuilder, SchemaBuilder, TableBuilder};
+    ///
+    /// let share = ShareBuilder::new("my-share").build();
+    /// let schema = SchemaBuilder::new(share, "my-schema").build();
+    /// let table = TableBuilder::new(schema, "my-table", "my-storage-path").start_version(3).build();
+    /// assert_eq!(table.start_version(), Some(3));
+    /// ```
+    pub fn start_version(&self) -> Option<u64> {
+        self.start_version
+    }
+
+    /// Returns the comment of `self`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use delta_sharing_server::protocol::securable::{ShareBuilder, SchemaBuilder, TableBuilder};
+    ///
+    /// let share = ShareBuilder::new("my-share").build();
+    /// let schema = SchemaBuilder::new(share, "my-schema").build();
+    /// let table = TableBuilder::new(schema, "my-table", "my-storage-path").comment("daily orders").build();
+    /// assert_eq!(table.comment(), Some("daily orders"));
+    /// ```
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
 }
 
 /// Builder for `Table`
@@ -360,6 +490,9 @@ pub struct TableBuilder {
     id: Option<String>,
     storage_path: String,
     format: Option<String>,
+    history_shared: bool,
+    start_version: Option<u64>,
+    comment: Option<String>,
 }
 
 impl TableBuilder {
@@ -375,6 +508,9 @@ impl TableBuilder {
             id: None,
             storage_path: storage_path.into(),
             format: None,
+            history_shared: true,
+            start_version: None,
+            comment: None,
         }
     }
 
@@ -402,6 +538,36 @@ impl TableBuilder {
         self
     }
 
+    /// Sets whether the history of the table is shared
+    pub fn history_shared(mut self, history_shared: bool) -> Self {
+        self.history_shared = history_shared;
+        self
+    }
+
+    /// Sets the first shared version of the table
+    pub fn start_version(mut self, start_version: u64) -> Self {
+        self.start_version = Some(start_version);
+        self
+    }
+
+    /// Sets the first shared version of the table
+    pub fn set_start_version(mut self, start_version: Option<u64>) -> Self {
+        self.start_version = start_version;
+        self
+    }
+
+    /// Sets the comment of the table
+    pub fn comment<S: Into<String>>(mut self, comment: S) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Sets the comment of the table
+    pub fn set_comment<S: Into<String>>(mut self, comment: Option<S>) -> Self {
+        self.comment = comment.map(Into::into);
+        self
+    }
+
     /// Builds a `Table` from the current `TableBuilder`
     pub fn build(self) -> Table {
         Table {
@@ -410,6 +576,9 @@ impl TableBuilder {
             id: self.id,
             storage_path: self.storage_path,
             format: self.format.unwrap_or_else(|| "DELTA".to_string()),
+            history_shared: self.history_shared,
+            start_version: self.start_version,
+            comment: self.comment,
         }
     }
 }
@@ -89,6 +89,12 @@ impl<T> List<T> {
         self.items.push(item);
     }
 
+    /// Retain only the items in the `List` for which the predicate holds.
+    /// The token to resume fetching from the collection is left untouched.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: F) {
+        self.items.retain(f);
+    }
+
     /// Retrieve all items in the list.
     pub fn items(&self) -> &[T] {
         self.items.as_ref()
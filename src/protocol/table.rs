@@ -4,35 +4,97 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use super::action::{Add, Cdf, File, Metadata, Protocol, Remove};
+use super::predicate::Predicate;
+use super::share::ListCursor;
 
 /// Requested table version.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Version {
     /// Latest table version.
     Latest,
+    /// Specific table version number.
+    Number(u64),
     /// Earliest table version after the specified timestamp.
     Timestamp(DateTime<Utc>),
 }
 
 /// Requested range of table version.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum VersionRange {
     /// Range of table versions represented by start and end version number.
     Version {
-        /// First timestamp that must be returned in the range.
+        /// First version that must be returned in the range.
         start: u64,
-        /// Last timestamp that must be returned in the range.
+        /// Last version that must be returned in the range.
         end: u64,
     },
     /// Range of table versions represented by start and end timestamp.
     Timestamp {
-        /// First version must be the earliest after the start timestamp.
+        /// First version must be the earliest at or after the start timestamp.
         start: DateTime<Utc>,
-        /// Last version must be the earliest after the end timestamp.
+        /// Last version must be the latest at or before the end timestamp.
         end: DateTime<Utc>,
     },
 }
 
+/// Requested table version of a table data query.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TableDataVersion {
+    /// Data files of a single table version.
+    Snapshot(Version),
+    /// Data files added in a range of table versions.
+    Since {
+        /// First version of which the added data files must be returned.
+        start: u64,
+        /// Last version of which the added data files must be returned. An
+        /// open range ends at the latest table version.
+        end: Option<u64>,
+    },
+}
+
+/// Query for the data files of a table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableDataQuery {
+    /// Requested table version.
+    pub version: TableDataVersion,
+    /// Number of rows the client wants to read, on a best effort basis.
+    pub limit: Option<u64>,
+    /// Predicate that the rows the client wants to read satisfy.
+    pub predicate: Option<Predicate>,
+    /// Requested page of the data files, in which the maximum number of
+    /// results is the maximum number of data files.
+    pub page: ListCursor,
+    /// Whether the response must include a token to refresh the urls of the
+    /// data files.
+    pub include_refresh_token: bool,
+    /// Token of an earlier query of which the urls of the data files must be
+    /// refreshed. It replaces the version, limit and predicate of the query.
+    pub refresh_token: Option<String>,
+}
+
+impl Default for TableDataQuery {
+    fn default() -> Self {
+        Self {
+            version: TableDataVersion::Snapshot(Version::Latest),
+            limit: None,
+            predicate: None,
+            page: ListCursor::default(),
+            include_refresh_token: false,
+            refresh_token: None,
+        }
+    }
+}
+
+/// Query for the change data files of a table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableChangesQuery {
+    /// Requested range of table versions.
+    pub range: VersionRange,
+    /// Requested page of the change data files, in which the maximum number
+    /// of results is the maximum number of data files.
+    pub page: ListCursor,
+}
+
 /// Table version number.
 pub type TableVersionNumber = u64;
 
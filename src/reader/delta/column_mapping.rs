@@ -0,0 +1,216 @@
+//! Translation of physical column names to logical column names.
+//!
+//! With column mapping mode `name` or `id`, the data files of a Delta table use
+//! physical column names that differ from the names in the table schema. The
+//! partition values and statistics in the transaction log use the physical
+//! names as well, and are translated before they are shared in the parquet
+//! response format.
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+const MODE_KEY: &str = "delta.columnMapping.mode";
+const PHYSICAL_NAME_KEY: &str = "delta.columnMapping.physicalName";
+const STATS_KEYS: [&str; 3] = ["minValues", "maxValues", "nullCount"];
+
+/// Mapping from the physical names of columns to their logical names.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(super) struct ColumnMapping {
+    fields: HashMap<String, (String, ColumnMapping)>,
+}
+
+impl ColumnMapping {
+    /// Derive the column mapping from the content of a `metaData` action.
+    ///
+    /// Tables without column mapping, or with column mapping mode `none`,
+    /// have no column mapping.
+    pub fn from_metadata(metadata: &Value) -> Option<Self> {
+        let mode = metadata.get("configuration")?.get(MODE_KEY)?.as_str()?;
+        if !matches!(mode, "name" | "id") {
+            return None;
+        }
+
+        let schema_string = metadata.get("schemaString")?.as_str()?;
+        let schema = serde_json::from_str::<Value>(schema_string).ok()?;
+        Some(Self::from_struct(&schema))
+    }
+
+    fn from_struct(struct_type: &Value) -> Self {
+        let fields = struct_type
+            .get("fields")
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        let fields = fields
+            .iter()
+            .filter_map(|field| {
+                let logical_name = field.get("name")?.as_str()?;
+                let physical_name = field
+                    .get("metadata")
+                    .and_then(|metadata| metadata.get(PHYSICAL_NAME_KEY))
+                    .and_then(Value::as_str)
+                    .unwrap_or(logical_name);
+                let nested = match field.get("type") {
+                    Some(nested_type) if nested_type.is_object() => Self::from_struct(nested_type),
+                    _ => Self::default(),
+                };
+                Some((physical_name.to_owned(), (logical_name.to_owned(), nested)))
+            })
+            .collect();
+        Self { fields }
+    }
+
+    fn logical_name<'a>(&'a self, physical_name: &'a str) -> (&'a str, Option<&'a Self>) {
+        match self.fields.get(physical_name) {
+            Some((logical_name, nested)) => (logical_name, Some(nested)),
+            None => (physical_name, None),
+        }
+    }
+
+    /// Translate the keys of the partition values of a data file to logical
+    /// column names.
+    pub fn logical_partition_values(
+        &self,
+        partition_values: HashMap<String, Option<String>>,
+    ) -> HashMap<String, Option<String>> {
+        partition_values
+            .into_iter()
+            .map(|(column, value)| (self.logical_name(&column).0.to_owned(), value))
+            .collect()
+    }
+
+    /// Translate the column names in the JSON statistics of a data file to
+    /// logical column names. Statistics that are not valid JSON are returned
+    /// unchanged.
+    pub fn logical_stats(&self, stats: &str) -> String {
+        let mut stats = match serde_json::from_str::<Value>(stats) {
+            Ok(Value::Object(stats)) => stats,
+            _ => return stats.to_owned(),
+        };
+        for key in STATS_KEYS {
+            if let Some(Value::Object(values)) = stats.remove(key) {
+                stats.insert(key.to_owned(), Value::Object(self.logical_values(values)));
+            }
+        }
+        Value::Object(stats).to_string()
+    }
+
+    fn logical_values(&self, values: Map<String, Value>) -> Map<String, Value> {
+        values
+            .into_iter()
+            .map(|(column, value)| {
+                let (logical_name, nested) = self.logical_name(&column);
+                let value = match (value, nested) {
+                    (Value::Object(nested_values), Some(nested)) => {
+                        Value::Object(nested.logical_values(nested_values))
+                    }
+                    (value, _) => value,
+                };
+                (logical_name.to_owned(), value)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn metadata(mode: &str) -> Value {
+        let schema = json!({
+            "type": "struct",
+            "fields": [
+                {
+                    "name": "date",
+                    "type": "date",
+                    "nullable": true,
+                    "metadata": {
+                        "delta.columnMapping.id": 1,
+                        "delta.columnMapping.physicalName": "col-5f422f40-de70-45b2-88ab-1d5c90e94db1",
+                    },
+                },
+                {
+                    "name": "address",
+                    "type": {
+                        "type": "struct",
+                        "fields": [{
+                            "name": "city",
+                            "type": "string",
+                            "nullable": true,
+                            "metadata": {
+                                "delta.columnMapping.id": 3,
+                                "delta.columnMapping.physicalName": "col-a7f4159c-53be-4cb0-b81a-f7e5240cfc49",
+                            },
+                        }],
+                    },
+                    "nullable": true,
+                    "metadata": {
+                        "delta.columnMapping.id": 2,
+                        "delta.columnMapping.physicalName": "col-3f1f4e5e-41b9-4b1c-a0b4-2d5d38c1c1a4",
+                    },
+                },
+            ],
+        });
+        json!({
+            "id": "f8d5c169-3d01-4ca3-ad9e-7dc3355aedb2",
+            "schemaString": schema.to_string(),
+            "partitionColumns": ["date"],
+            "configuration": {"delta.columnMapping.mode": mode},
+        })
+    }
+
+    #[test]
+    fn column_mapping_mode() {
+        assert!(ColumnMapping::from_metadata(&metadata("name")).is_some());
+        assert!(ColumnMapping::from_metadata(&metadata("id")).is_some());
+        assert!(ColumnMapping::from_metadata(&metadata("none")).is_none());
+        assert!(ColumnMapping::from_metadata(&json!({"configuration": {}})).is_none());
+    }
+
+    #[test]
+    fn translate_partition_values() {
+        let mapping = ColumnMapping::from_metadata(&metadata("name")).unwrap();
+        let partition_values = HashMap::from([(
+            String::from("col-5f422f40-de70-45b2-88ab-1d5c90e94db1"),
+            Some(String::from("2023-01-01")),
+        )]);
+        assert_eq!(
+            mapping.logical_partition_values(partition_values),
+            HashMap::from([(String::from("date"), Some(String::from("2023-01-01")))])
+        );
+    }
+
+    #[test]
+    fn translate_stats() {
+        let mapping = ColumnMapping::from_metadata(&metadata("id")).unwrap();
+        let stats = json!({
+            "numRecords": 1,
+            "minValues": {
+                "col-5f422f40-de70-45b2-88ab-1d5c90e94db1": "2023-01-01",
+                "col-3f1f4e5e-41b9-4b1c-a0b4-2d5d38c1c1a4": {
+                    "col-a7f4159c-53be-4cb0-b81a-f7e5240cfc49": "Amsterdam",
+                },
+            },
+            "nullCount": {"col-5f422f40-de70-45b2-88ab-1d5c90e94db1": 0},
+        });
+
+        let logical_stats =
+            serde_json::from_str::<Value>(&mapping.logical_stats(&stats.to_string())).unwrap();
+        assert_eq!(
+            logical_stats,
+            json!({
+                "numRecords": 1,
+                "minValues": {
+                    "date": "2023-01-01",
+                    "address": {"city": "Amsterdam"},
+                },
+                "nullCount": {"date": 0},
+            })
+        );
+        assert_eq!(mapping.logical_stats("not json"), "not json");
+    }
+}
@@ -0,0 +1,126 @@
+//! Location of the deletion vectors of data files in a Delta table.
+//!
+//! A deletion vector descriptor in the transaction log either embeds the
+//! deletion vector inline (`i`), refers to a file by an absolute path (`p`), or
+//! refers to a file in the table directory by a Z85 encoded UUID (`u`).
+
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::reader::TableReaderError;
+
+const Z85_ALPHABET: &[u8; 85] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+/// Resolve the url of the file that stores a deletion vector.
+///
+/// Inline deletion vectors are not stored in a separate file, in which case
+/// no url is returned.
+pub(super) fn deletion_vector_url(
+    storage_path: &str,
+    descriptor: &Value,
+) -> Result<Option<String>, TableReaderError> {
+    let storage_type = descriptor.get("storageType").and_then(Value::as_str);
+    let path_or_inline_dv = descriptor.get("pathOrInlineDv").and_then(Value::as_str);
+    match (storage_type, path_or_inline_dv) {
+        (Some("i"), _) => Ok(None),
+        (Some("p"), Some(path)) => Ok(Some(path.to_owned())),
+        (Some("u"), Some(encoded)) => {
+            let relative_path =
+                relative_path(encoded).ok_or_else(|| invalid_descriptor(descriptor))?;
+            Ok(Some(format!(
+                "{}/{}",
+                storage_path.trim_end_matches('/'),
+                relative_path
+            )))
+        }
+        _ => Err(invalid_descriptor(descriptor)),
+    }
+}
+
+fn invalid_descriptor(descriptor: &Value) -> TableReaderError {
+    TableReaderError::CorruptedLog {
+        reason: format!("invalid deletion vector descriptor `{}`", descriptor),
+    }
+}
+
+/// Derive the path relative to the table root of a deletion vector file from
+/// its random prefix followed by its Z85 encoded UUID.
+fn relative_path(encoded: &str) -> Option<String> {
+    let split = encoded.len().checked_sub(20)?;
+    let (prefix, encoded_uuid) = (encoded.get(..split)?, encoded.get(split..)?);
+    let uuid = Uuid::from_slice(&z85_decode(encoded_uuid)?).ok()?;
+
+    let file_name = format!("deletion_vector_{}.bin", uuid.hyphenated());
+    match prefix {
+        "" => Some(file_name),
+        prefix => Some(format!("{}/{}", prefix, file_name)),
+    }
+}
+
+fn z85_decode(encoded: &str) -> Option<Vec<u8>> {
+    if !encoded.len().is_multiple_of(5) {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(encoded.len() / 5 * 4);
+    for chunk in encoded.as_bytes().chunks(5) {
+        let mut value = 0u64;
+        for c in chunk {
+            let digit = Z85_ALPHABET.iter().position(|z| z == c)?;
+            value = value * 85 + digit as u64;
+        }
+        bytes.extend_from_slice(&u32::try_from(value).ok()?.to_be_bytes());
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn relative_deletion_vector_url() {
+        let descriptor = json!({
+            "storageType": "u",
+            "pathOrInlineDv": "ab^-aqEH.-t@S}K{vb[*k^",
+            "offset": 4,
+            "sizeInBytes": 40,
+            "cardinality": 6,
+        });
+        assert_eq!(
+            deletion_vector_url("s3://bucket/table/", &descriptor).unwrap(),
+            Some(String::from(
+                "s3://bucket/table/ab/deletion_vector_d2c639aa-8816-431a-aaf6-d3fe2512ff61.bin"
+            ))
+        );
+    }
+
+    #[test]
+    fn absolute_and_inline_deletion_vector_url() {
+        let descriptor = json!({
+            "storageType": "p",
+            "pathOrInlineDv": "s3://bucket/dv/deletion_vector_1.bin",
+        });
+        assert_eq!(
+            deletion_vector_url("s3://bucket/table", &descriptor).unwrap(),
+            Some(String::from("s3://bucket/dv/deletion_vector_1.bin"))
+        );
+
+        let descriptor = json!({"storageType": "i", "pathOrInlineDv": "wi5b=000010000siXQKl0rr91000f55c8Xg0@@D72lkbi5=-{L"});
+        assert_eq!(
+            deletion_vector_url("s3://bucket/table", &descriptor).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn invalid_deletion_vector_descriptor() {
+        let descriptor = json!({"storageType": "u", "pathOrInlineDv": "too short"});
+        assert!(deletion_vector_url("s3://bucket/table", &descriptor).is_err());
+        let descriptor = json!({"storageType": "x", "pathOrInlineDv": "path"});
+        assert!(deletion_vector_url("s3://bucket/table", &descriptor).is_err());
+    }
+}
@@ -0,0 +1,809 @@
+//! Replay of the Delta transaction log on the level of raw JSON actions.
+//!
+//! The Delta response format forwards the actions of the transaction log as
+//! they were written, including fields that the `deltalake` crate does not
+//! model such as table features and deletion vectors. The log is therefore
+//! replayed without interpreting the actions beyond what is needed to
+//! reconstruct a snapshot of the table.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use deltalake::parquet::file::reader::{FileReader, SerializedFileReader};
+use deltalake::{DeltaTableError, ObjectStore, ObjectStoreError, Path};
+use futures::TryStreamExt;
+use percent_encoding::percent_decode_str;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::reader::TableReaderError;
+
+const LOG_DIR: &str = "_delta_log";
+
+/// Snapshot of a Delta table at a specific version.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct LogSnapshot {
+    /// Version of the table snapshot.
+    pub version: u64,
+    /// The content of the `protocol` action.
+    pub protocol: Value,
+    /// The content of the `metaData` action.
+    pub metadata: Value,
+    /// The content of the `add` actions of the data files, ordered by path.
+    pub files: Vec<Value>,
+    /// Approximate size of the actions of the snapshot in JSON, in bytes.
+    pub size: usize,
+}
+
+/// File in the `_delta_log` directory that is used for log replay.
+#[derive(Debug, Clone, PartialEq)]
+enum LogFile {
+    Commit { version: u64 },
+    Checkpoint { version: u64, part: u64, parts: u64 },
+}
+
+impl LogFile {
+    /// Parse the name of a commit file, e.g. `00000000000000000010.json`, or
+    /// of a (multi-part) checkpoint file, e.g.
+    /// `00000000000000000010.checkpoint.0000000001.0000000002.parquet`.
+    fn from_name(name: &str) -> Option<Self> {
+        let digits = name.get(..20)?;
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let version = digits.parse().ok()?;
+
+        match &name[20..] {
+            ".json" => Some(Self::Commit { version }),
+            ".checkpoint.parquet" => Some(Self::Checkpoint {
+                version,
+                part: 1,
+                parts: 1,
+            }),
+            suffix => {
+                let (part, parts) = suffix
+                    .strip_prefix(".checkpoint.")?
+                    .strip_suffix(".parquet")?
+                    .split_once('.')?;
+                Some(Self::Checkpoint {
+                    version,
+                    part: part.parse().ok()?,
+                    parts: parts.parse().ok()?,
+                })
+            }
+        }
+    }
+}
+
+/// Listing of the commits and checkpoints in the transaction log.
+#[derive(Debug, Default)]
+struct LogListing {
+    commits: BTreeSet<u64>,
+    checkpoints: BTreeMap<u64, Vec<(u64, u64, Path)>>,
+}
+
+impl LogListing {
+    fn insert(&mut self, file: LogFile, path: Path) {
+        match file {
+            LogFile::Commit { version } => {
+                self.commits.insert(version);
+            }
+            LogFile::Checkpoint {
+                version,
+                part,
+                parts,
+            } => self
+                .checkpoints
+                .entry(version)
+                .or_default()
+                .push((part, parts, path)),
+        }
+    }
+
+    fn latest_version(&self) -> Option<u64> {
+        let latest_commit = self.commits.last().copied();
+        let latest_checkpoint = self.checkpoints.keys().last().copied();
+        latest_commit.max(latest_checkpoint)
+    }
+
+    /// Find the latest checkpoint at or before `version` of which all parts
+    /// are present, together with the paths of its parts in order.
+    fn checkpoint_before(&self, version: u64) -> Option<(u64, Vec<Path>)> {
+        self.checkpoints
+            .range(..=version)
+            .rev()
+            .find_map(|(checkpoint_version, files)| {
+                complete_checkpoint(files).map(|paths| (*checkpoint_version, paths))
+            })
+    }
+}
+
+fn complete_checkpoint(files: &[(u64, u64, Path)]) -> Option<Vec<Path>> {
+    let part_counts = files
+        .iter()
+        .map(|(_, parts, _)| *parts)
+        .collect::<BTreeSet<_>>();
+    part_counts.into_iter().find_map(|parts| {
+        let paths = files
+            .iter()
+            .filter(|(_, p, _)| *p == parts)
+            .map(|(part, _, path)| (*part, path.clone()))
+            .collect::<BTreeMap<_, _>>();
+        let complete = (1..=parts).all(|part| paths.contains_key(&part));
+        complete.then(|| paths.into_values().collect())
+    })
+}
+
+/// State of the table while the transaction log is replayed.
+#[derive(Debug, Default)]
+struct LogReplay {
+    protocol: Option<Value>,
+    metadata: Option<Value>,
+    files: BTreeMap<String, Value>,
+    /// Approximate size of the data file actions in JSON.
+    files_size: usize,
+}
+
+impl LogReplay {
+    /// Resume the replay from a snapshot of the table.
+    fn from_snapshot(snapshot: &LogSnapshot) -> Self {
+        Self {
+            protocol: Some(snapshot.protocol.clone()),
+            metadata: Some(snapshot.metadata.clone()),
+            files: snapshot
+                .files
+                .iter()
+                .map(|file| (file_key(file), file.clone()))
+                .collect(),
+            files_size: snapshot.files.iter().map(json_size).sum(),
+        }
+    }
+
+    fn apply(&mut self, action: Value) {
+        let (kind, content) = match split_action(action) {
+            Some(action) => action,
+            None => return,
+        };
+        match kind.as_str() {
+            "protocol" => self.protocol = Some(content),
+            "metaData" => self.metadata = Some(content),
+            "add" => {
+                self.files_size += json_size(&content);
+                if let Some(replaced) = self.files.insert(file_key(&content), content) {
+                    self.files_size -= json_size(&replaced);
+                }
+            }
+            "remove" => {
+                if let Some(removed) = self.files.remove(&file_key(&content)) {
+                    self.files_size -= json_size(&removed);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn into_snapshot(self, version: u64) -> Result<LogSnapshot, TableReaderError> {
+        match (self.protocol, self.metadata) {
+            (Some(protocol), Some(metadata)) => Ok(LogSnapshot {
+                version,
+                size: json_size(&protocol) + json_size(&metadata) + self.files_size,
+                protocol,
+                metadata,
+                files: self.files.into_values().collect(),
+            }),
+            _ => Err(TableReaderError::CorruptedLog {
+                reason: String::from("the log has no protocol or metadata"),
+            }),
+        }
+    }
+}
+
+/// Approximate the size of a JSON value when serialized, without actually
+/// serializing it.
+pub(super) fn json_size(value: &Value) -> usize {
+    match value {
+        Value::Null | Value::Bool(_) | Value::Number(_) => 8,
+        Value::String(string) => string.len() + 2,
+        Value::Array(values) => {
+            values
+                .iter()
+                .map(|value| json_size(value) + 1)
+                .sum::<usize>()
+                + 2
+        }
+        Value::Object(fields) => {
+            fields
+                .iter()
+                .map(|(name, value)| name.len() + json_size(value) + 4)
+                .sum::<usize>()
+                + 2
+        }
+    }
+}
+
+/// Split a single action into its kind, e.g. `add`, and its content.
+pub(super) fn split_action(action: Value) -> Option<(String, Value)> {
+    match action {
+        Value::Object(map) => map.into_iter().find(|(_, content)| content.is_object()),
+        _ => None,
+    }
+}
+
+/// Derive the key that identifies a logical data file in the table.
+///
+/// A data file is identified by its decoded path together with the unique id
+/// of its deletion vector, if any.
+pub(super) fn file_key(file_action: &Value) -> String {
+    let path = file_action
+        .get("path")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let mut key = percent_decode_str(path).decode_utf8_lossy().into_owned();
+    if let Some(dv_id) = file_action
+        .get("deletionVector")
+        .and_then(deletion_vector_id)
+    {
+        key.push('#');
+        key.push_str(&dv_id);
+    }
+    key
+}
+
+/// Derive the unique id of a deletion vector descriptor.
+pub(super) fn deletion_vector_id(descriptor: &Value) -> Option<String> {
+    let storage_type = descriptor.get("storageType")?.as_str()?;
+    let path_or_inline_dv = descriptor.get("pathOrInlineDv")?.as_str()?;
+    let mut id = format!("{}{}", storage_type, path_or_inline_dv);
+    if let Some(offset) = descriptor.get("offset").and_then(Value::as_i64) {
+        id.push_str(&format!("@{}", offset));
+    }
+    Some(id)
+}
+
+/// Retrieve the latest version of the table, if the transaction log has any
+/// versions at all.
+pub(super) async fn latest_version(
+    store: &dyn ObjectStore,
+) -> Result<Option<u64>, TableReaderError> {
+    Ok(list_log(store, None).await?.latest_version())
+}
+
+/// Find the earliest commit in the transaction log, if the transaction log
+/// has any commits at all.
+pub(super) async fn earliest_commit(
+    store: &dyn ObjectStore,
+) -> Result<Option<u64>, TableReaderError> {
+    // The first commit is usually kept, which saves listing the whole log.
+    match store.head(&commit_path(0)).await {
+        Ok(_) => return Ok(Some(0)),
+        Err(ObjectStoreError::NotFound { .. }) => {}
+        Err(err) => return Err(err.into()),
+    }
+    Ok(list_log_files(store, None).await?.commits.first().copied())
+}
+
+/// Replay the transaction log up to and including `version`, if the
+/// transaction log has any versions at all.
+pub(super) async fn load_snapshot(
+    store: &dyn ObjectStore,
+    version: u64,
+) -> Result<Option<LogSnapshot>, TableReaderError> {
+    let listing = list_log(store, Some(version)).await?;
+    match listing.latest_version() {
+        None => return Ok(None),
+        Some(latest) if version > latest => {
+            return Err(TableReaderError::VersionNotFound { version })
+        }
+        Some(_) => {}
+    }
+
+    let mut replay = LogReplay::default();
+    let first_commit = match listing.checkpoint_before(version) {
+        Some((checkpoint_version, paths)) => {
+            for path in paths {
+                for action in read_checkpoint(store, &path).await? {
+                    replay.apply(action);
+                }
+            }
+            checkpoint_version + 1
+        }
+        None => 0,
+    };
+
+    for commit_version in first_commit..=version {
+        if !listing.commits.contains(&commit_version) {
+            // Without a checkpoint, the version cannot be reconstructed once
+            // the commits before it have been cleaned up.
+            if first_commit == 0 {
+                return Err(TableReaderError::VersionNotFound { version });
+            }
+            return Err(TableReaderError::CorruptedLog {
+                reason: format!("commit {} is missing", commit_version),
+            });
+        }
+        for action in read_commit(store, commit_version).await? {
+            replay.apply(action);
+        }
+    }
+
+    replay.into_snapshot(version).map(Some)
+}
+
+/// Bring a snapshot of the table up to `version` by replaying the commits
+/// after the version of the snapshot, or by replaying the log from the last
+/// checkpoint when it is more recent than the snapshot.
+pub(super) async fn update_snapshot(
+    store: &dyn ObjectStore,
+    snapshot: &LogSnapshot,
+    version: u64,
+) -> Result<LogSnapshot, TableReaderError> {
+    if version < snapshot.version {
+        return Err(TableReaderError::Other {
+            reason: format!(
+                "snapshot of version {} cannot be updated to version {}",
+                snapshot.version, version
+            ),
+        });
+    }
+
+    // A newer checkpoint saves replaying the commits before it, which may
+    // also have been cleaned up in the meantime.
+    if let Some(checkpoint_version) = last_checkpoint(store).await? {
+        if snapshot.version < checkpoint_version && checkpoint_version <= version {
+            if let Some(snapshot) = load_snapshot(store, version).await? {
+                return Ok(snapshot);
+            }
+        }
+    }
+
+    let mut replay = LogReplay::from_snapshot(snapshot);
+    for commit_version in snapshot.version + 1..=version {
+        for action in read_commit(store, commit_version).await? {
+            replay.apply(action);
+        }
+    }
+    replay.into_snapshot(version)
+}
+
+/// Read the actions of a single commit.
+pub(super) async fn read_commit(
+    store: &dyn ObjectStore,
+    version: u64,
+) -> Result<Vec<Value>, TableReaderError> {
+    let path = commit_path(version);
+    let bytes = match store.get(&path).await {
+        Ok(result) => result.bytes().await?,
+        Err(ObjectStoreError::NotFound { .. }) => {
+            return Err(TableReaderError::VersionNotFound { version })
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    bytes
+        .split(|b| *b == b'\n')
+        .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+        .map(|line| {
+            serde_json::from_slice(line).map_err(|_| TableReaderError::CorruptedLog {
+                reason: format!("commit {} is not valid JSON", version),
+            })
+        })
+        .collect()
+}
+
+/// Retrieve the path of a commit file relative to the table root.
+pub(super) fn commit_path(version: u64) -> Path {
+    Path::from(format!("{}/{:020}.json", LOG_DIR, version))
+}
+
+/// Content of the `_delta_log/_last_checkpoint` file.
+#[derive(Debug, Deserialize)]
+struct LastCheckpoint {
+    version: u64,
+}
+
+/// Read the version of the last checkpoint from the `_last_checkpoint` file.
+///
+/// The file is only a hint, so a missing or unreadable file is ignored.
+async fn last_checkpoint(store: &dyn ObjectStore) -> Result<Option<u64>, TableReaderError> {
+    let path = Path::from(format!("{}/_last_checkpoint", LOG_DIR));
+    let bytes = match store.get(&path).await {
+        Ok(result) => result.bytes().await?,
+        Err(ObjectStoreError::NotFound { .. }) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    Ok(serde_json::from_slice::<LastCheckpoint>(&bytes)
+        .ok()
+        .map(|checkpoint| checkpoint.version))
+}
+
+/// List the files of the transaction log that are needed to replay the log
+/// up to `version`, or up to the latest version when no version is given.
+///
+/// When the last checkpoint can be used for the replay, only the files from
+/// that checkpoint onwards are listed. Otherwise the whole log is listed.
+async fn list_log(
+    store: &dyn ObjectStore,
+    version: Option<u64>,
+) -> Result<LogListing, TableReaderError> {
+    if let Some(checkpoint_version) = last_checkpoint(store).await? {
+        let target = version.unwrap_or(u64::MAX);
+        if checkpoint_version <= target {
+            let offset = Path::from(format!("{}/{:020}", LOG_DIR, checkpoint_version));
+            let listing = list_log_files(store, Some(&offset)).await?;
+            if listing.checkpoint_before(target).is_some() {
+                return Ok(listing);
+            }
+        }
+    }
+    list_log_files(store, None).await
+}
+
+/// List the commits and checkpoints in the transaction log. A missing
+/// `_delta_log` directory results in an empty listing.
+async fn list_log_files(
+    store: &dyn ObjectStore,
+    offset: Option<&Path>,
+) -> Result<LogListing, TableReaderError> {
+    let prefix = Path::from(LOG_DIR);
+    let files = match offset {
+        Some(offset) => store.list_with_offset(Some(&prefix), offset).await,
+        None => store.list(Some(&prefix)).await,
+    };
+    let mut files = match files {
+        Ok(files) => files,
+        Err(ObjectStoreError::NotFound { .. }) => return Ok(LogListing::default()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut listing = LogListing::default();
+    loop {
+        let meta = match files.try_next().await {
+            Ok(Some(meta)) => meta,
+            Ok(None) => break,
+            Err(ObjectStoreError::NotFound { .. }) if listing.latest_version().is_none() => {
+                return Ok(LogListing::default())
+            }
+            Err(err) => return Err(err.into()),
+        };
+        if let Some(file) = meta.location.filename().and_then(LogFile::from_name) {
+            listing.insert(file, meta.location);
+        }
+    }
+    Ok(listing)
+}
+
+async fn read_checkpoint(
+    store: &dyn ObjectStore,
+    path: &Path,
+) -> Result<Vec<Value>, TableReaderError> {
+    let bytes = match store.get(path).await {
+        Ok(result) => result.bytes().await?,
+        Err(ObjectStoreError::NotFound { .. }) => {
+            return Err(TableReaderError::CorruptedLog {
+                reason: format!("checkpoint {} is missing", path),
+            })
+        }
+        Err(err) => return Err(err.into()),
+    };
+    let reader = SerializedFileReader::new(bytes).map_err(DeltaTableError::from)?;
+    let rows = reader.get_row_iter(None).map_err(DeltaTableError::from)?;
+
+    Ok(rows
+        .filter_map(|row| checkpoint_action(row.to_json_value()))
+        .collect())
+}
+
+/// Convert a row of a checkpoint into a single action.
+///
+/// Every row of a checkpoint has a column for each kind of action, of which
+/// only one is set. Unset fields are dropped, and statistics that are only
+/// available in their parsed form are serialized back to JSON.
+fn checkpoint_action(row: Value) -> Option<Value> {
+    let (kind, content) = split_action(row)?;
+    let mut fields = match content {
+        Value::Object(fields) => fields,
+        _ => return None,
+    };
+
+    let stats_parsed = fields.remove("stats_parsed");
+    let mut content = strip_nulls(fields);
+    content.retain(|key, _| !key.ends_with("_parsed"));
+    if let Some(Value::Object(dv)) = content.remove("deletionVector") {
+        content.insert(String::from("deletionVector"), strip_nulls(dv).into());
+    }
+    if let Some(stats) = stats_parsed.filter(|stats| !stats.is_null()) {
+        content
+            .entry("stats")
+            .or_insert_with(|| Value::String(stats.to_string()));
+    }
+
+    let mut action = Map::new();
+    action.insert(kind, Value::Object(content));
+    Some(Value::Object(action))
+}
+
+fn strip_nulls(fields: Map<String, Value>) -> Map<String, Value> {
+    fields.into_iter().filter(|(_, v)| !v.is_null()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_log_file_names() {
+        assert_eq!(
+            LogFile::from_name("00000000000000000010.json"),
+            Some(LogFile::Commit { version: 10 })
+        );
+        assert_eq!(
+            LogFile::from_name("00000000000000000010.checkpoint.parquet"),
+            Some(LogFile::Checkpoint {
+                version: 10,
+                part: 1,
+                parts: 1
+            })
+        );
+        assert_eq!(
+            LogFile::from_name("00000000000000000010.checkpoint.0000000002.0000000003.parquet"),
+            Some(LogFile::Checkpoint {
+                version: 10,
+                part: 2,
+                parts: 3
+            })
+        );
+        assert_eq!(LogFile::from_name("_last_checkpoint"), None);
+        assert_eq!(LogFile::from_name("00000000000000000010.crc"), None);
+        assert_eq!(LogFile::from_name("+0000000000000000010.json"), None);
+    }
+
+    #[test]
+    fn select_complete_checkpoint() {
+        let mut listing = LogListing::default();
+        for name in [
+            "00000000000000000010.checkpoint.parquet",
+            "00000000000000000020.checkpoint.0000000001.0000000002.parquet",
+            "00000000000000000020.checkpoint.0000000002.0000000002.parquet",
+            "00000000000000000030.checkpoint.0000000001.0000000002.parquet",
+        ] {
+            let path = Path::from(format!("_delta_log/{}", name));
+            listing.insert(LogFile::from_name(name).unwrap(), path);
+        }
+
+        assert_eq!(listing.checkpoint_before(9), None);
+        assert_eq!(listing.checkpoint_before(15).unwrap().0, 10);
+        let (version, paths) = listing.checkpoint_before(35).unwrap();
+        assert_eq!(version, 20);
+        assert_eq!(
+            paths,
+            vec![
+                Path::from(
+                    "_delta_log/00000000000000000020.checkpoint.0000000001.0000000002.parquet"
+                ),
+                Path::from(
+                    "_delta_log/00000000000000000020.checkpoint.0000000002.0000000002.parquet"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn replay_actions() {
+        let mut replay = LogReplay::default();
+        let actions = vec![
+            json!({"commitInfo": {"timestamp": 1}}),
+            json!({"protocol": {"minReaderVersion": 1, "minWriterVersion": 2}}),
+            json!({"metaData": {"id": "a"}}),
+            json!({"add": {"path": "b.parquet", "size": 1}}),
+            json!({"add": {"path": "a.parquet", "size": 2}}),
+            json!({"add": {"path": "c%20d.parquet", "size": 3}}),
+            json!({"remove": {"path": "c d.parquet"}}),
+            json!({"protocol": {"minReaderVersion": 3, "minWriterVersion": 7}}),
+        ];
+        for action in actions {
+            replay.apply(action);
+        }
+
+        let snapshot = replay.into_snapshot(1).unwrap();
+        assert_eq!(
+            snapshot.protocol,
+            json!({"minReaderVersion": 3, "minWriterVersion": 7})
+        );
+        assert_eq!(snapshot.metadata, json!({"id": "a"}));
+        assert_eq!(
+            snapshot.files,
+            vec![
+                json!({"path": "a.parquet", "size": 2}),
+                json!({"path": "b.parquet", "size": 1}),
+            ]
+        );
+    }
+
+    #[test]
+    fn replay_without_metadata() {
+        let mut replay = LogReplay::default();
+        replay.apply(json!({"protocol": {"minReaderVersion": 1}}));
+        assert!(replay.into_snapshot(0).is_err());
+    }
+
+    #[test]
+    fn convert_checkpoint_row() {
+        let row = json!({
+            "txn": null,
+            "add": {
+                "path": "a.parquet",
+                "partitionValues": {"date": null},
+                "size": 1,
+                "stats": null,
+                "stats_parsed": {"numRecords": 1},
+                "tags": null,
+                "deletionVector": null,
+            },
+            "remove": null,
+            "metaData": null,
+        });
+        assert_eq!(
+            checkpoint_action(row),
+            Some(json!({"add": {
+                "path": "a.parquet",
+                "partitionValues": {"date": null},
+                "size": 1,
+                "stats": "{\"numRecords\":1}",
+            }}))
+        );
+        assert_eq!(
+            checkpoint_action(json!({"add": null, "remove": null})),
+            None
+        );
+    }
+
+    #[test]
+    fn deletion_vector_file_key() {
+        let add = json!({
+            "path": "a.parquet",
+            "deletionVector": {
+                "storageType": "u",
+                "pathOrInlineDv": "ab^-aqEH.-t@S}K{vb[*k^",
+                "offset": 4,
+                "sizeInBytes": 40,
+                "cardinality": 6,
+            },
+        });
+        assert_eq!(file_key(&add), "a.parquet#uab^-aqEH.-t@S}K{vb[*k^@4");
+        assert_eq!(file_key(&json!({"path": "a.parquet"})), "a.parquet");
+    }
+
+    #[tokio::test]
+    async fn load_snapshot_from_commits() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_dir = dir.path().join(LOG_DIR);
+        std::fs::create_dir(&log_dir).unwrap();
+        std::fs::write(
+            log_dir.join("00000000000000000000.json"),
+            concat!(
+                r#"{"protocol":{"minReaderVersion":3,"minWriterVersion":7,"readerFeatures":["deletionVectors"]}}"#,
+                "\n",
+                r#"{"metaData":{"id":"a","format":{"provider":"parquet","options":{}},"schemaString":"{}","partitionColumns":[],"configuration":{}}}"#,
+                "\n",
+                r#"{"add":{"path":"a.parquet","partitionValues":{},"size":1,"modificationTime":0,"dataChange":true}}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            log_dir.join("00000000000000000001.json"),
+            r#"{"remove":{"path":"a.parquet","dataChange":true}}"#,
+        )
+        .unwrap();
+
+        let table = deltalake::DeltaTableBuilder::from_uri(dir.path().to_str().unwrap())
+            .build()
+            .unwrap();
+        let store = table.object_store();
+
+        assert_eq!(latest_version(store.as_ref()).await.unwrap(), Some(1));
+        let snapshot = load_snapshot(store.as_ref(), 0).await.unwrap().unwrap();
+        assert_eq!(snapshot.version, 0);
+        assert_eq!(snapshot.files.len(), 1);
+        assert_eq!(
+            snapshot.protocol["readerFeatures"],
+            json!(["deletionVectors"])
+        );
+        let latest_snapshot = load_snapshot(store.as_ref(), 1).await.unwrap().unwrap();
+        assert_eq!(latest_snapshot.version, 1);
+        assert!(latest_snapshot.files.is_empty());
+        assert_eq!(
+            load_snapshot(store.as_ref(), 2).await,
+            Err(TableReaderError::VersionNotFound { version: 2 })
+        );
+
+        assert_eq!(
+            update_snapshot(store.as_ref(), &snapshot, 1).await.unwrap(),
+            latest_snapshot
+        );
+        assert_eq!(
+            update_snapshot(store.as_ref(), &snapshot, 2).await,
+            Err(TableReaderError::VersionNotFound { version: 2 })
+        );
+    }
+
+    #[tokio::test]
+    async fn update_snapshot_from_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_dir = dir.path().join(LOG_DIR);
+        std::fs::create_dir(&log_dir).unwrap();
+        std::fs::write(
+            log_dir.join("00000000000000000000.json"),
+            concat!(
+                r#"{"protocol":{"minReaderVersion":1,"minWriterVersion":2}}"#,
+                "\n",
+                r#"{"metaData":{"id":"a","format":{"provider":"parquet","options":{}},"schemaString":"{\"type\":\"struct\",\"fields\":[{\"name\":\"id\",\"type\":\"long\",\"nullable\":true,\"metadata\":{}}]}","partitionColumns":[],"configuration":{},"createdTime":0}}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+        for version in 1..3 {
+            std::fs::write(
+                log_dir.join(format!("{:020}.json", version)),
+                format!(
+                    r#"{{"add":{{"path":"{}.parquet","partitionValues":{{}},"size":1,"modificationTime":0,"dataChange":true}}}}"#,
+                    version
+                ),
+            )
+            .unwrap();
+        }
+
+        let table_uri = dir.path().to_str().unwrap();
+        let table = deltalake::open_table(table_uri).await.unwrap();
+        deltalake::checkpoints::create_checkpoint(&table)
+            .await
+            .unwrap();
+        let store = table.object_store();
+        let snapshot = load_snapshot(store.as_ref(), 0).await.unwrap().unwrap();
+        let latest_snapshot = load_snapshot(store.as_ref(), 2).await.unwrap().unwrap();
+
+        // The commits before the checkpoint are no longer needed.
+        for version in 1..3 {
+            std::fs::remove_file(log_dir.join(format!("{:020}.json", version))).unwrap();
+        }
+        let updated_snapshot = update_snapshot(store.as_ref(), &snapshot, 2).await.unwrap();
+        assert_eq!(updated_snapshot.version, 2);
+        assert_eq!(updated_snapshot.files.len(), 2);
+        assert_eq!(updated_snapshot.size, latest_snapshot.size);
+    }
+
+    #[tokio::test]
+    async fn list_log_from_last_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_dir = dir.path().join(LOG_DIR);
+        std::fs::create_dir(&log_dir).unwrap();
+        for version in 0..3 {
+            std::fs::write(log_dir.join(format!("{:020}.json", version)), "").unwrap();
+        }
+        std::fs::write(
+            log_dir.join("_last_checkpoint"),
+            r#"{"version":1,"size":1}"#,
+        )
+        .unwrap();
+
+        let table = deltalake::DeltaTableBuilder::from_uri(dir.path().to_str().unwrap())
+            .build()
+            .unwrap();
+        let store = table.object_store();
+
+        // Without the checkpoint the hint cannot be used.
+        let listing = list_log(store.as_ref(), None).await.unwrap();
+        assert_eq!(listing.commits, BTreeSet::from([0, 1, 2]));
+
+        std::fs::write(log_dir.join("00000000000000000001.checkpoint.parquet"), "").unwrap();
+        let listing = list_log(store.as_ref(), None).await.unwrap();
+        assert_eq!(listing.commits, BTreeSet::from([1, 2]));
+        assert_eq!(listing.latest_version(), Some(2));
+        let listing = list_log(store.as_ref(), Some(1)).await.unwrap();
+        assert_eq!(listing.commits, BTreeSet::from([1, 2]));
+
+        // Versions before the last checkpoint need the whole log.
+        let listing = list_log(store.as_ref(), Some(0)).await.unwrap();
+        assert_eq!(listing.commits, BTreeSet::from([0, 1, 2]));
+    }
+}
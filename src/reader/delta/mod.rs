@@ -0,0 +1,1185 @@
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use deltalake::{DeltaTableBuilder, DeltaTableError, ObjectStore, ObjectStoreError};
+use futures::stream::{self, StreamExt};
+use percent_encoding::percent_decode_str;
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::protocol::action::{
+    AddBuilder, CdfBuilder, DeltaProtocol, FileBuilder, Metadata, MetadataBuilder, Protocol,
+    ProtocolBuilder, RemoveBuilder,
+};
+use crate::protocol::predicate::Predicate;
+use crate::protocol::table::{
+    TableMetadata, TableVersionNumber, UnsignedDataFile, UnsignedTableData, Version, VersionRange,
+};
+
+use super::{TableReader, TableReaderError, UnsignedTableDataStream};
+use column_mapping::ColumnMapping;
+use log_replay::LogSnapshot;
+use snapshot_cache::SnapshotCache;
+
+mod column_mapping;
+mod deletion_vector;
+mod log_replay;
+mod snapshot_cache;
+
+/// Default total size in bytes of the table snapshots kept in the snapshot
+/// cache.
+pub const DEFAULT_SNAPSHOT_CACHE_CAPACITY: usize = 256 * 1024 * 1024;
+
+/// Default window in which the latest version of a table is reused instead of
+/// listing the transaction log again.
+pub const DEFAULT_SNAPSHOT_CACHE_STALENESS: Duration = Duration::from_secs(5);
+
+/// TableReader implementation for the Delta Lake format.
+///
+/// Snapshots of the tables are cached, such that a query of a cached table
+/// version does not replay the transaction log, and a query of a later
+/// version only replays the commits after the cached snapshot.
+#[derive(Debug, Clone)]
+pub struct DeltaTableReader {
+    cache: Arc<SnapshotCache>,
+}
+
+impl DeltaTableReader {
+    /// Create a new instance of the Delta Lake TableReader.
+    ///
+    /// Table snapshots up to a total size of
+    /// [`DEFAULT_SNAPSHOT_CACHE_CAPACITY`] bytes are cached, and the latest
+    /// version of a table is reused for [`DEFAULT_SNAPSHOT_CACHE_STALENESS`].
+    pub fn new() -> Self {
+        Self::with_snapshot_cache(
+            DEFAULT_SNAPSHOT_CACHE_CAPACITY,
+            DEFAULT_SNAPSHOT_CACHE_STALENESS,
+        )
+    }
+
+    /// Create a new instance of the Delta Lake TableReader with a custom
+    /// snapshot cache.
+    ///
+    /// Table snapshots are cached up to a total size of `capacity` bytes,
+    /// approximated by the size of their actions in JSON, after which the
+    /// least recently used snapshots are evicted. The latest version of a
+    /// table is reused for queries within the `staleness` window after it
+    /// was looked up, so new commits may take that long to become visible.
+    /// A zero `staleness` looks up the latest version for every query.
+    pub fn with_snapshot_cache(capacity: usize, staleness: Duration) -> Self {
+        Self {
+            cache: Arc::new(SnapshotCache::new(capacity, staleness)),
+        }
+    }
+
+    /// Retrieve the latest version of a table. A version that was looked up
+    /// within the staleness window is reused.
+    async fn latest_version(
+        &self,
+        store: &dyn ObjectStore,
+        storage_path: &str,
+    ) -> Result<u64, TableReaderError> {
+        if let Some(version) = self.cache.latest_version(storage_path) {
+            return Ok(version);
+        }
+        let version = log_replay::latest_version(store).await?.ok_or_else(|| {
+            TableReaderError::TableNotFound {
+                storage_path: storage_path.to_owned(),
+            }
+        })?;
+        self.cache.set_latest_version(storage_path, version);
+        Ok(version)
+    }
+
+    /// Retrieve the snapshot of a table in a version, or in its latest
+    /// version when no version is given.
+    ///
+    /// A cached snapshot of an earlier version is brought up to date by
+    /// replaying the commits after it, instead of replaying the whole log.
+    /// This is what `DeltaTable::update` does for the typed table state of
+    /// `deltalake`, which cannot be used here because the snapshots keep the
+    /// raw actions that the Delta response format forwards.
+    async fn snapshot(
+        &self,
+        storage_path: &str,
+        version: Option<u64>,
+    ) -> Result<Arc<LogSnapshot>, TableReaderError> {
+        let store = object_store(storage_path)?;
+        let version = match version {
+            Some(version) => version,
+            None => self.latest_version(store.as_ref(), storage_path).await?,
+        };
+        if let Some(snapshot) = self.cache.get(storage_path, version) {
+            return Ok(snapshot);
+        }
+
+        let updated = match self.cache.latest_before(storage_path, version) {
+            // The commits after the cached snapshot may have been cleaned up,
+            // in which case the log is replayed from the latest checkpoint.
+            Some(cached) => log_replay::update_snapshot(store.as_ref(), &cached, version)
+                .await
+                .ok(),
+            None => None,
+        };
+        let snapshot = match updated {
+            Some(snapshot) => snapshot,
+            None => log_replay::load_snapshot(store.as_ref(), version)
+                .await?
+                .ok_or_else(|| TableReaderError::TableNotFound {
+                    storage_path: storage_path.to_owned(),
+                })?,
+        };
+        let snapshot = Arc::new(snapshot);
+        self.cache.insert(storage_path, snapshot.clone());
+        Ok(snapshot)
+    }
+}
+
+impl Default for DeltaTableReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl TableReader for DeltaTableReader {
+    async fn get_table_version(
+        &self,
+        storage_path: &str,
+        version: Version,
+    ) -> Result<TableVersionNumber, TableReaderError> {
+        match version {
+            Version::Latest => {
+                let store = object_store(storage_path)?;
+                self.latest_version(store.as_ref(), storage_path).await
+            }
+            Version::Number(version) => {
+                let snapshot = self.snapshot(storage_path, Some(version)).await?;
+                Ok(snapshot.version)
+            }
+            Version::Timestamp(ts) => {
+                let store = object_store(storage_path)?;
+                let latest_version = self.latest_version(store.as_ref(), storage_path).await?;
+                let versions = version_span(store.as_ref(), latest_version).await?;
+                version_at_timestamp(store.as_ref(), versions, ts).await
+            }
+        }
+    }
+
+    async fn get_table_metadata(
+        &self,
+        storage_path: &str,
+    ) -> Result<TableMetadata, TableReaderError> {
+        let snapshot = self.snapshot(storage_path, None).await?;
+
+        Ok(TableMetadata {
+            version: snapshot.version,
+            protocol: table_protocol(&snapshot.protocol)?,
+            metadata: table_metadata(&snapshot.metadata)?,
+        })
+    }
+
+    async fn get_table_data(
+        &self,
+        storage_path: &str,
+        version: u64,
+        limit: Option<u64>,
+        predicate: Option<Predicate>,
+    ) -> Result<UnsignedTableData, TableReaderError> {
+        self.get_table_data_stream(storage_path, version, limit, predicate)
+            .await?
+            .collect()
+            .await
+    }
+
+    async fn get_table_data_stream(
+        &self,
+        storage_path: &str,
+        version: u64,
+        limit: Option<u64>,
+        predicate: Option<Predicate>,
+    ) -> Result<UnsignedTableDataStream, TableReaderError> {
+        let snapshot = self.snapshot(storage_path, Some(version)).await?;
+        let version = snapshot.version;
+        let protocol = table_protocol(&snapshot.protocol)?;
+        let metadata = table_metadata(&snapshot.metadata)?;
+        let column_mapping = ColumnMapping::from_metadata(&snapshot.metadata);
+        let table_files = snapshot_files(
+            storage_path.to_owned(),
+            snapshot,
+            limit,
+            predicate,
+            column_mapping,
+        );
+
+        Ok(UnsignedTableDataStream {
+            version,
+            protocol,
+            metadata,
+            data: stream::iter(table_files).boxed(),
+        })
+    }
+
+    async fn get_table_data_since(
+        &self,
+        storage_path: &str,
+        starting_version: u64,
+        ending_version: u64,
+    ) -> Result<UnsignedTableData, TableReaderError> {
+        let store = object_store(storage_path)?;
+        let latest_version = self.latest_version(store.as_ref(), storage_path).await?;
+        if ending_version > latest_version {
+            return Err(TableReaderError::VersionNotFound {
+                version: ending_version,
+            });
+        }
+        if starting_version > ending_version {
+            return Err(TableReaderError::VersionNotFound {
+                version: starting_version,
+            });
+        }
+
+        // The protocol and metadata are reported as of the end of the range.
+        let snapshot = self.snapshot(storage_path, Some(ending_version)).await?;
+        let column_mapping = ColumnMapping::from_metadata(&snapshot.metadata);
+
+        let mut additions = vec![];
+        for version in starting_version..=ending_version {
+            let (timestamp, actions) = read_commit(store.as_ref(), version).await?;
+            additions.extend(commit_additions(
+                storage_path,
+                version,
+                timestamp,
+                actions,
+                column_mapping.as_ref(),
+            )?);
+        }
+
+        Ok(UnsignedTableData {
+            version: starting_version,
+            protocol: table_protocol(&snapshot.protocol)?,
+            metadata: table_metadata(&snapshot.metadata)?,
+            data: additions,
+        })
+    }
+
+    async fn get_table_changes(
+        &self,
+        storage_path: &str,
+        range: VersionRange,
+    ) -> Result<UnsignedTableData, TableReaderError> {
+        let store = object_store(storage_path)?;
+        let latest_version = self.latest_version(store.as_ref(), storage_path).await?;
+
+        let (start, end) = match range {
+            VersionRange::Version { start, end } => {
+                if end > latest_version {
+                    return Err(TableReaderError::VersionNotFound { version: end });
+                }
+                (start, end)
+            }
+            VersionRange::Timestamp { start, end } => {
+                let versions = version_span(store.as_ref(), latest_version).await?;
+                let start_version = count_versions_before(
+                    store.as_ref(),
+                    versions,
+                    start.timestamp_millis(),
+                    false,
+                )
+                .await?;
+                // When the earliest commits have been cleaned up, versions
+                // committed at or after the start may no longer exist.
+                let removed_history = start_version == versions.0 && versions.0 > 0;
+                if start_version > latest_version || removed_history {
+                    return Err(TableReaderError::TimestampOutOfRange {
+                        timestamp: start.to_rfc3339(),
+                    });
+                }
+                let end_version = version_at_timestamp(store.as_ref(), versions, end).await?;
+                (start_version, end_version)
+            }
+        };
+        if start > end {
+            return Err(TableReaderError::VersionNotFound { version: start });
+        }
+
+        // The protocol and metadata are reported as of the end of the range.
+        let snapshot = self.snapshot(storage_path, Some(end)).await?;
+        let column_mapping = ColumnMapping::from_metadata(&snapshot.metadata);
+
+        let mut changes = vec![];
+        for version in start..=end {
+            let (timestamp, actions) = read_commit(store.as_ref(), version).await?;
+            changes.extend(commit_changes(
+                storage_path,
+                version,
+                timestamp,
+                actions,
+                column_mapping.as_ref(),
+            )?);
+        }
+
+        Ok(UnsignedTableData {
+            version: start,
+            protocol: table_protocol(&snapshot.protocol)?,
+            metadata: table_metadata(&snapshot.metadata)?,
+            data: changes,
+        })
+    }
+}
+
+/// Fields of the `add`, `cdc` and `remove` actions in the Delta transaction
+/// log that are part of the data files in a response.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileAction {
+    path: String,
+    #[serde(default)]
+    partition_values: HashMap<String, Option<String>>,
+    #[serde(default)]
+    size: Option<u64>,
+    #[serde(default)]
+    data_change: bool,
+    #[serde(default)]
+    stats: Option<String>,
+}
+
+impl FileAction {
+    fn from_value(action: &Value) -> Result<Self, TableReaderError> {
+        serde_json::from_value(action.clone()).map_err(|e| TableReaderError::CorruptedLog {
+            reason: format!("invalid file action: {}", e),
+        })
+    }
+
+    /// Translate the partition values and statistics from physical to logical
+    /// column names. The raw action keeps the physical names, so tables with
+    /// column mapping remain readable in the Delta response format.
+    fn with_column_mapping(mut self, column_mapping: Option<&ColumnMapping>) -> Self {
+        if let Some(column_mapping) = column_mapping {
+            self.partition_values = column_mapping.logical_partition_values(self.partition_values);
+            self.stats = self.stats.map(|stats| column_mapping.logical_stats(&stats));
+        }
+        self
+    }
+}
+
+/// Fields of the `metaData` action in the Delta transaction log.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeltaMetadata {
+    id: String,
+    schema_string: String,
+    #[serde(default)]
+    partition_columns: Vec<String>,
+    #[serde(default)]
+    configuration: HashMap<String, Option<String>>,
+}
+
+fn object_store(storage_path: &str) -> Result<Arc<dyn ObjectStore>, TableReaderError> {
+    let delta_table = DeltaTableBuilder::from_uri(storage_path).build()?;
+    let store: Arc<dyn ObjectStore> = delta_table.object_store();
+    Ok(store)
+}
+
+/// Reader features of tables that can be shared. The data files are read by
+/// the client, so the server only needs to understand the features that
+/// change how the transaction log is replayed.
+const SUPPORTED_READER_FEATURES: [&str; 4] = [
+    "columnMapping",
+    "deletionVectors",
+    "timestampNtz",
+    "vacuumProtocolCheck",
+];
+
+fn table_protocol(protocol: &Value) -> Result<Protocol, TableReaderError> {
+    let delta_protocol =
+        serde_json::from_value::<DeltaProtocol>(protocol.clone()).map_err(|e| {
+            TableReaderError::CorruptedLog {
+                reason: format!("invalid protocol action: {}", e),
+            }
+        })?;
+    if let Some(feature) = delta_protocol
+        .reader_features()
+        .into_iter()
+        .find(|feature| !SUPPORTED_READER_FEATURES.contains(feature))
+    {
+        return Err(TableReaderError::UnsupportedReaderFeature {
+            feature: feature.to_owned(),
+        });
+    }
+    Ok(ProtocolBuilder::new()
+        .min_reader_version(delta_protocol.min_reader_version())
+        .delta_protocol(delta_protocol)
+        .build())
+}
+
+fn table_metadata(metadata: &Value) -> Result<Metadata, TableReaderError> {
+    let delta_metadata =
+        serde_json::from_value::<DeltaMetadata>(metadata.clone()).map_err(|e| {
+            TableReaderError::CorruptedLog {
+                reason: format!("invalid metaData action: {}", e),
+            }
+        })?;
+    let configuration = delta_metadata
+        .configuration
+        .into_iter()
+        .map(|(key, value)| (key, value.unwrap_or_default()))
+        .collect();
+
+    Ok(
+        MetadataBuilder::new(delta_metadata.id, delta_metadata.schema_string)
+            .partition_columns(delta_metadata.partition_columns)
+            .configuration(configuration)
+            .delta_metadata(metadata.clone())
+            .build(),
+    )
+}
+
+fn data_file_url(storage_path: &str, file_path: &str) -> String {
+    format!("{}/{}", storage_path.trim_end_matches('/'), file_path)
+}
+
+/// Derive a stable identifier for a data file from its path relative to the
+/// table root.
+///
+/// The identifier is the hex encoded prefix of the SHA-256 digest of the
+/// decoded path, so the same file has the same id in snapshot queries and in
+/// change data feed queries.
+fn file_id(file_path: &str) -> String {
+    let decoded_path = percent_decode_str(file_path).decode_utf8_lossy();
+    let digest = Sha256::digest(decoded_path.as_bytes());
+    hex::encode(&digest[..16])
+}
+
+/// Convert the `add` actions of the table snapshot into data files as they
+/// are consumed.
+///
+/// Files without a record count in their statistics are not counted towards
+/// the limit, so they never cause matching rows to be left out.
+fn snapshot_files(
+    storage_path: String,
+    snapshot: Arc<LogSnapshot>,
+    limit: Option<u64>,
+    predicate: Option<Predicate>,
+    column_mapping: Option<ColumnMapping>,
+) -> impl Iterator<Item = Result<UnsignedDataFile, TableReaderError>> + Send {
+    (0..snapshot.files.len())
+        .map(move |index| {
+            let add = snapshot.files[index].clone();
+            let file = FileAction::from_value(&add)?.with_column_mapping(column_mapping.as_ref());
+            Ok::<_, TableReaderError>((file, add))
+        })
+        .filter(move |file| match (file, &predicate) {
+            (Ok((file, _)), Some(predicate)) => {
+                predicate.may_match(&file.partition_values, file.stats.as_deref())
+            }
+            _ => true,
+        })
+        .scan(0, move |row_count, file| {
+            if limit.is_some_and(|limit| *row_count >= limit) {
+                return None;
+            }
+            if let Ok((file, _)) = &file {
+                *row_count += file
+                    .stats
+                    .as_deref()
+                    .and_then(num_records)
+                    .unwrap_or_default();
+            }
+            Some(file)
+        })
+        .map(move |file| file.and_then(|(file, add)| table_file(&storage_path, file, add)))
+}
+
+/// Convert an `add` action of the table snapshot into a data file.
+fn table_file(
+    storage_path: &str,
+    file: FileAction,
+    add: Value,
+) -> Result<UnsignedDataFile, TableReaderError> {
+    let url = data_file_url(storage_path, &file.path);
+    let deletion_vector = deletion_vector_file(storage_path, &add)?;
+    let mut builder = FileBuilder::new(url, file_id(&file.path))
+        .partition_values(file.partition_values)
+        .size(file.size.unwrap_or_default())
+        .delta_action(add);
+    if let Some(stats) = file.stats {
+        builder = builder.stats(stats);
+    }
+    if let Some((id, url)) = deletion_vector {
+        builder = builder.deletion_vector(id, url);
+    }
+    Ok(builder.build().into())
+}
+
+/// Retrieve the id and url of the deletion vector file of a file action, if
+/// the deletion vector of the file is not stored inline.
+fn deletion_vector_file(
+    storage_path: &str,
+    file_action: &Value,
+) -> Result<Option<(String, String)>, TableReaderError> {
+    let descriptor = match file_action.get("deletionVector") {
+        Some(descriptor) if !descriptor.is_null() => descriptor,
+        _ => return Ok(None),
+    };
+    let url = deletion_vector::deletion_vector_url(storage_path, descriptor)?;
+    Ok(url.map(|url| (file_id(&url), url)))
+}
+
+/// Retrieve the number of records in a data file from its JSON statistics.
+fn num_records(stats: &str) -> Option<u64> {
+    serde_json::from_str::<Value>(stats)
+        .ok()?
+        .get("numRecords")?
+        .as_u64()
+}
+
+/// Read the actions of a single commit together with the commit timestamp in
+/// milliseconds since the unix epoch.
+///
+/// The timestamp is taken from the `commitInfo` action when present and falls
+/// back to the modification time of the commit file otherwise.
+async fn read_commit(
+    store: &dyn ObjectStore,
+    version: u64,
+) -> Result<(i64, Vec<Value>), TableReaderError> {
+    let actions = log_replay::read_commit(store, version).await?;
+
+    let commit_timestamp = actions.iter().find_map(|action| {
+        action
+            .get("commitInfo")?
+            .get("timestamp")
+            .and_then(Value::as_i64)
+    });
+    let timestamp = match commit_timestamp {
+        Some(ts) => ts,
+        None => match store.head(&log_replay::commit_path(version)).await {
+            Ok(commit_meta) => commit_meta.last_modified.timestamp_millis(),
+            Err(ObjectStoreError::NotFound { .. }) => {
+                return Err(TableReaderError::VersionNotFound { version })
+            }
+            Err(err) => return Err(err.into()),
+        },
+    };
+
+    Ok((timestamp, actions))
+}
+
+/// Retrieve the earliest and the latest version of the table of which the
+/// commit is still in the transaction log. Log cleanup removes the commits
+/// of the earliest versions once they are covered by a checkpoint.
+async fn version_span(
+    store: &dyn ObjectStore,
+    latest_version: u64,
+) -> Result<(u64, u64), TableReaderError> {
+    let earliest_version = log_replay::earliest_commit(store)
+        .await?
+        .unwrap_or(latest_version);
+    Ok((earliest_version.min(latest_version), latest_version))
+}
+
+/// Find the last version of the table that was committed at or before
+/// `timestamp`.
+async fn version_at_timestamp(
+    store: &dyn ObjectStore,
+    versions: (u64, u64),
+    timestamp: DateTime<Utc>,
+) -> Result<u64, TableReaderError> {
+    let count = count_versions_before(store, versions, timestamp.timestamp_millis(), true).await?;
+    if count > versions.0 {
+        Ok(count - 1)
+    } else {
+        Err(TableReaderError::TimestampOutOfRange {
+            timestamp: timestamp.to_rfc3339(),
+        })
+    }
+}
+
+/// Count the table versions that were committed before `timestamp`, or at
+/// `timestamp` as well when `inclusive` is set, searching the commits of the
+/// `(earliest, latest)` versions. The versions before the earliest version
+/// are always counted.
+///
+/// Commit timestamps are assumed to increase with the table version, which
+/// allows for a binary search over the transaction log.
+async fn count_versions_before(
+    store: &dyn ObjectStore,
+    (earliest_version, latest_version): (u64, u64),
+    timestamp: i64,
+    inclusive: bool,
+) -> Result<u64, TableReaderError> {
+    let (mut low, mut high) = (earliest_version, latest_version + 1);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let (commit_ts, _) = read_commit(store, mid).await?;
+        if commit_ts < timestamp || (inclusive && commit_ts == timestamp) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    Ok(low)
+}
+
+/// Convert the actions of a single commit into change data files.
+///
+/// When the commit contains change data files only those are returned. Other
+/// commits are represented by the `add` and `remove` actions that changed the
+/// data of the table.
+fn commit_changes(
+    storage_path: &str,
+    version: u64,
+    timestamp: i64,
+    actions: Vec<Value>,
+    column_mapping: Option<&ColumnMapping>,
+) -> Result<Vec<UnsignedDataFile>, TableReaderError> {
+    let has_cdc_files = actions.iter().any(|action| action.get("cdc").is_some());
+    let timestamp = timestamp.to_string();
+
+    let mut changes = vec![];
+    for (kind, action) in actions.into_iter().filter_map(log_replay::split_action) {
+        if !matches!(kind.as_str(), "cdc" | "add" | "remove") {
+            continue;
+        }
+        let file = FileAction::from_value(&action)?.with_column_mapping(column_mapping);
+        let url = data_file_url(storage_path, &file.path);
+        let id = file_id(&file.path);
+        let size = file.size.unwrap_or_default();
+
+        match kind.as_str() {
+            "cdc" => {
+                let cdf = CdfBuilder::new(url, id, size, version, timestamp.clone())
+                    .partition_values(file.partition_values)
+                    .delta_action(action)
+                    .build();
+                changes.push(cdf.into());
+            }
+            "add" if !has_cdc_files && file.data_change => {
+                changes.push(added_file(storage_path, version, &timestamp, file, action)?);
+            }
+            "remove" if !has_cdc_files && file.data_change => {
+                let deletion_vector = deletion_vector_file(storage_path, &action)?;
+                let mut builder = RemoveBuilder::new(url, id, size)
+                    .partition_values(file.partition_values)
+                    .version(version)
+                    .timestamp(timestamp.clone())
+                    .delta_action(action);
+                if let Some((dv_id, dv_url)) = deletion_vector {
+                    builder = builder.deletion_vector(dv_id, dv_url);
+                }
+                changes.push(builder.build().into());
+            }
+            _ => {}
+        }
+    }
+    Ok(changes)
+}
+
+/// Only the `add` actions that changed the data of the table are returned.
+/// Files added by a rearrangement of the data, e.g. a compaction, hold no new
+/// rows.
+fn commit_additions(
+    storage_path: &str,
+    version: u64,
+    timestamp: i64,
+    actions: Vec<Value>,
+    column_mapping: Option<&ColumnMapping>,
+) -> Result<Vec<UnsignedDataFile>, TableReaderError> {
+    let timestamp = timestamp.to_string();
+
+    let mut additions = vec![];
+    for (kind, action) in actions.into_iter().filter_map(log_replay::split_action) {
+        if kind != "add" {
+            continue;
+        }
+        let file = FileAction::from_value(&action)?.with_column_mapping(column_mapping);
+        if file.data_change {
+            additions.push(added_file(storage_path, version, &timestamp, file, action)?);
+        }
+    }
+    Ok(additions)
+}
+
+fn added_file(
+    storage_path: &str,
+    version: u64,
+    timestamp: &str,
+    file: FileAction,
+    action: Value,
+) -> Result<UnsignedDataFile, TableReaderError> {
+    let deletion_vector = deletion_vector_file(storage_path, &action)?;
+    let mut builder = AddBuilder::new(data_file_url(storage_path, &file.path), file_id(&file.path))
+        .partition_values(file.partition_values)
+        .size(file.size.unwrap_or_default())
+        .version(version)
+        .timestamp(timestamp)
+        .delta_action(action);
+    if let Some(stats) = file.stats {
+        builder = builder.stats(stats);
+    }
+    if let Some((dv_id, dv_url)) = deletion_vector {
+        builder = builder.deletion_vector(dv_id, dv_url);
+    }
+    Ok(builder.build().into())
+}
+
+impl From<DeltaTableError> for TableReaderError {
+    fn from(value: DeltaTableError) -> Self {
+        match value {
+            DeltaTableError::ObjectStore { source } => source.into(),
+            DeltaTableError::NotATable(storage_path)
+            | DeltaTableError::InvalidTableLocation(storage_path) => {
+                TableReaderError::TableNotFound { storage_path }
+            }
+            DeltaTableError::InvalidVersion(version) => TableReaderError::VersionNotFound {
+                version: version.max(0) as u64,
+            },
+            DeltaTableError::InvalidJsonLog { .. }
+            | DeltaTableError::LoadCheckpoint { .. }
+            | DeltaTableError::ApplyLog { .. }
+            | DeltaTableError::Parquet { .. }
+            | DeltaTableError::InvalidAction { .. } => TableReaderError::CorruptedLog {
+                reason: value.to_string(),
+            },
+            _ => TableReaderError::Other {
+                reason: value.to_string(),
+            },
+        }
+    }
+}
+
+impl From<ObjectStoreError> for TableReaderError {
+    fn from(value: ObjectStoreError) -> Self {
+        let reason = value.to_string();
+        if is_permission_denied(&value) {
+            TableReaderError::PermissionDenied { reason }
+        } else if is_throttled(&value) {
+            TableReaderError::Throttled { reason }
+        } else {
+            // Missing objects are not reported as a missing table here: only
+            // a transaction log without any versions means the table does
+            // not exist, which is determined when the log is listed.
+            TableReaderError::Other { reason }
+        }
+    }
+}
+
+/// Iterate over an object store error and the errors that caused it.
+fn error_chain(error: &ObjectStoreError) -> impl Iterator<Item = &(dyn StdError + 'static)> {
+    std::iter::successors(Some(error as &(dyn StdError + 'static)), |&error| {
+        error.source()
+    })
+}
+
+/// Retrieve the status of the response of the storage service that caused an
+/// object store error, if any.
+///
+/// The errors of the HTTP client used by the object stores are not exposed,
+/// so the status is taken from their message, e.g. `HTTP status client
+/// error (403 Forbidden) for url (...)`.
+fn response_status(error: &ObjectStoreError) -> Option<StatusCode> {
+    error_chain(error).find_map(|source| {
+        let message = source.to_string();
+        let (_, status) = message.split_once("HTTP status ")?;
+        let (_, status) = status.split_once(" error (")?;
+        StatusCode::from_bytes(status.get(..3)?.as_bytes()).ok()
+    })
+}
+
+/// Determine whether an object store error was caused by missing
+/// permissions, either on the storage service or on the local file system.
+fn is_permission_denied(error: &ObjectStoreError) -> bool {
+    let io_permission_denied = error_chain(error)
+        .filter_map(|source| source.downcast_ref::<io::Error>())
+        .any(|source| source.kind() == io::ErrorKind::PermissionDenied);
+    io_permission_denied
+        || matches!(
+            response_status(error),
+            Some(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+        )
+}
+
+/// Determine whether an object store error was caused by the storage service
+/// throttling requests.
+fn is_throttled(error: &ObjectStoreError) -> bool {
+    matches!(
+        response_status(error),
+        Some(StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn stable_file_id() {
+        let id = file_id("date=2023-01-01/part-00000.snappy.parquet");
+        assert_eq!(id.len(), 32);
+        assert_eq!(id, file_id("date=2023-01-01/part-00000.snappy.parquet"));
+        assert_eq!(id, file_id("date%3D2023-01-01/part-00000.snappy.parquet"));
+        assert_ne!(id, file_id("date=2023-01-02/part-00000.snappy.parquet"));
+    }
+
+    #[test]
+    fn table_file_metadata() {
+        let add = json!({
+            "path": "date=2023-01-01/part-00000.snappy.parquet",
+            "partitionValues": {"date": "2023-01-01"},
+            "size": 573,
+            "modificationTime": 1652140000000i64,
+            "dataChange": true,
+            "stats": r#"{"numRecords":1}"#,
+        });
+        let file = FileAction::from_value(&add).unwrap();
+
+        match table_file("s3://bucket/table/", file, add.clone()).unwrap() {
+            UnsignedDataFile::File(file) => {
+                assert_eq!(
+                    file.url(),
+                    "s3://bucket/table/date=2023-01-01/part-00000.snappy.parquet"
+                );
+                assert_eq!(
+                    file.id(),
+                    file_id("date=2023-01-01/part-00000.snappy.parquet")
+                );
+                assert_eq!(file.size(), 573);
+                assert_eq!(
+                    file.partition_values().get("date"),
+                    Some(&Some(String::from("2023-01-01")))
+                );
+                assert_eq!(file.stats(), Some(r#"{"numRecords":1}"#));
+                assert_eq!(file.delta_action(), Some(&add));
+            }
+            other => panic!("unexpected data file: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn table_file_column_mapping() {
+        let schema = json!({
+            "type": "struct",
+            "fields": [{
+                "name": "date",
+                "type": "date",
+                "nullable": true,
+                "metadata": {
+                    "delta.columnMapping.id": 1,
+                    "delta.columnMapping.physicalName": "col-5f422f40",
+                },
+            }],
+        });
+        let metadata = json!({
+            "id": "f8d5c169-3d01-4ca3-ad9e-7dc3355aedb2",
+            "schemaString": schema.to_string(),
+            "partitionColumns": ["date"],
+            "configuration": {"delta.columnMapping.mode": "name"},
+        });
+        let add = json!({
+            "path": "col-5f422f40=2023-01-01/part-00000.snappy.parquet",
+            "partitionValues": {"col-5f422f40": "2023-01-01"},
+            "size": 573,
+            "modificationTime": 1652140000000i64,
+            "dataChange": true,
+            "stats": r#"{"numRecords":1,"nullCount":{"col-5f422f40":0}}"#,
+        });
+        let column_mapping = ColumnMapping::from_metadata(&metadata);
+        let file = FileAction::from_value(&add)
+            .unwrap()
+            .with_column_mapping(column_mapping.as_ref());
+
+        match table_file("s3://bucket/table", file, add.clone()).unwrap() {
+            UnsignedDataFile::File(file) => {
+                assert_eq!(
+                    file.partition_values(),
+                    &HashMap::from([(String::from("date"), Some(String::from("2023-01-01")))])
+                );
+                let stats = serde_json::from_str::<Value>(file.stats().unwrap()).unwrap();
+                assert_eq!(stats, json!({"numRecords": 1, "nullCount": {"date": 0}}));
+                assert_eq!(file.delta_action(), Some(&add));
+            }
+            other => panic!("unexpected data file: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn table_file_deletion_vector() {
+        let add = json!({
+            "path": "part-00000.snappy.parquet",
+            "partitionValues": {},
+            "size": 573,
+            "modificationTime": 1652140000000i64,
+            "dataChange": true,
+            "deletionVector": {
+                "storageType": "u",
+                "pathOrInlineDv": "ab^-aqEH.-t@S}K{vb[*k^",
+                "offset": 4,
+                "sizeInBytes": 40,
+                "cardinality": 6,
+            },
+        });
+        let file = FileAction::from_value(&add).unwrap();
+
+        match table_file("s3://bucket/table", file, add).unwrap() {
+            UnsignedDataFile::File(file) => {
+                let dv_url =
+                    "s3://bucket/table/ab/deletion_vector_d2c639aa-8816-431a-aaf6-d3fe2512ff61.bin";
+                let dv = file.deletion_vector().unwrap();
+                assert_eq!(dv.url(), dv_url);
+                assert_eq!(dv.id(), file_id(dv_url));
+            }
+            other => panic!("unexpected data file: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn table_protocol_and_metadata() {
+        let protocol = table_protocol(&json!({
+            "minReaderVersion": 3,
+            "minWriterVersion": 7,
+            "readerFeatures": ["deletionVectors"],
+            "writerFeatures": ["deletionVectors"],
+        }))
+        .unwrap();
+        assert_eq!(protocol.min_reader_version(), 3);
+        assert!(protocol
+            .delta_protocol()
+            .unwrap()
+            .has_reader_feature("deletionVectors"));
+        assert_eq!(
+            table_protocol(&json!({
+                "minReaderVersion": 3,
+                "minWriterVersion": 7,
+                "readerFeatures": ["deletionVectors", "v2Checkpoint"],
+            }))
+            .unwrap_err(),
+            TableReaderError::UnsupportedReaderFeature {
+                feature: String::from("v2Checkpoint")
+            }
+        );
+
+        let raw_metadata = json!({
+            "id": "f8d5c169-3d01-4ca3-ad9e-7dc3355aedb2",
+            "format": {"provider": "parquet", "options": {}},
+            "schemaString": "{}",
+            "partitionColumns": ["date"],
+            "configuration": {"delta.enableDeletionVectors": "true"},
+            "createdTime": 1652140000000i64,
+        });
+        let metadata = table_metadata(&raw_metadata).unwrap();
+        assert_eq!(metadata.id(), "f8d5c169-3d01-4ca3-ad9e-7dc3355aedb2");
+        assert_eq!(metadata.partition_columns(), &[String::from("date")]);
+        assert_eq!(
+            metadata.configuration().get("delta.enableDeletionVectors"),
+            Some(&String::from("true"))
+        );
+        assert_eq!(metadata.delta_metadata(), Some(&raw_metadata));
+    }
+
+    #[test]
+    fn commit_change_files() {
+        let actions = vec![
+            json!({"commitInfo": {"timestamp": 1652140000000i64}}),
+            json!({"add": {"path": "a.parquet", "partitionValues": {}, "size": 1, "modificationTime": 0, "dataChange": true}}),
+            json!({"add": {"path": "b.parquet", "partitionValues": {}, "size": 2, "modificationTime": 0, "dataChange": false}}),
+            json!({"remove": {"path": "c.parquet", "dataChange": true}}),
+        ];
+        let changes = commit_changes("s3://bucket/table", 1, 1652140000000, actions, None).unwrap();
+        assert_eq!(changes.len(), 2);
+        match &changes[0] {
+            UnsignedDataFile::Add(add) => {
+                assert_eq!(add.url, "s3://bucket/table/a.parquet");
+                assert_eq!(add.timestamp, "1652140000000");
+                assert_eq!(add.delta_action.as_ref().unwrap()["path"], "a.parquet");
+            }
+            other => panic!("unexpected data file: {:?}", other),
+        }
+        assert!(matches!(&changes[1], UnsignedDataFile::Remove(r) if r.size == 0));
+
+        let actions = vec![
+            json!({"add": {"path": "a.parquet", "partitionValues": {}, "size": 1, "modificationTime": 0, "dataChange": true}}),
+            json!({"cdc": {"path": "_change_data/cdc.parquet", "partitionValues": {}, "size": 3, "dataChange": false}}),
+        ];
+        let changes = commit_changes("s3://bucket/table", 2, 1652150000000, actions, None).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(&changes[0], UnsignedDataFile::Cdf(cdf) if cdf.size == 3));
+    }
+
+    #[test]
+    fn commit_addition_files() {
+        let actions = vec![
+            json!({"commitInfo": {"timestamp": 1652140000000i64}}),
+            json!({"add": {"path": "a.parquet", "partitionValues": {}, "size": 1, "modificationTime": 0, "dataChange": true, "stats": "{\"numRecords\":1}"}}),
+            json!({"add": {"path": "b.parquet", "partitionValues": {}, "size": 2, "modificationTime": 0, "dataChange": false}}),
+            json!({"remove": {"path": "c.parquet", "dataChange": true}}),
+            json!({"cdc": {"path": "_change_data/cdc.parquet", "partitionValues": {}, "size": 3, "dataChange": false}}),
+        ];
+        let additions =
+            commit_additions("s3://bucket/table", 3, 1652140000000, actions, None).unwrap();
+        assert_eq!(additions.len(), 1);
+        match &additions[0] {
+            UnsignedDataFile::Add(add) => {
+                assert_eq!(add.url, "s3://bucket/table/a.parquet");
+                assert_eq!(add.id, file_id("a.parquet"));
+                assert_eq!(add.size, 1);
+                assert_eq!(add.stats.as_deref(), Some(r#"{"numRecords":1}"#));
+                assert_eq!(add.version, 3);
+                assert_eq!(add.timestamp, "1652140000000");
+            }
+            other => panic!("unexpected data file: {:?}", other),
+        }
+    }
+
+    fn response_error(status: u16) -> ObjectStoreError {
+        let status = StatusCode::from_u16(status).unwrap();
+        let class = if status.is_client_error() {
+            "client"
+        } else {
+            "server"
+        };
+        let source = format!(
+            "response error \"request error\", after 0 retries: HTTP status {} error ({}) for url (https://bucket.s3.amazonaws.com/table/_delta_log/)",
+            class, status
+        );
+        ObjectStoreError::Generic {
+            store: "S3",
+            source: source.into(),
+        }
+    }
+
+    #[test]
+    fn object_store_error_kind() {
+        for status in [401, 403] {
+            assert!(matches!(
+                TableReaderError::from(response_error(status)),
+                TableReaderError::PermissionDenied { .. }
+            ));
+        }
+        let permission_denied = ObjectStoreError::Generic {
+            store: "LocalFileSystem",
+            source: Box::new(io::Error::new(io::ErrorKind::PermissionDenied, "denied")),
+        };
+        assert!(matches!(
+            TableReaderError::from(permission_denied),
+            TableReaderError::PermissionDenied { .. }
+        ));
+
+        for status in [429, 503] {
+            assert!(matches!(
+                TableReaderError::from(DeltaTableError::from(response_error(status))),
+                TableReaderError::Throttled { .. }
+            ));
+        }
+
+        // The status is also found in the errors that caused the error.
+        let nested = ObjectStoreError::Generic {
+            store: "S3",
+            source: Box::new(DeltaTableError::from(response_error(403))),
+        };
+        assert!(matches!(
+            TableReaderError::from(nested),
+            TableReaderError::PermissionDenied { .. }
+        ));
+
+        // A missing data file or checkpoint does not mean the table is missing.
+        let not_found = ObjectStoreError::NotFound {
+            path: String::from("_delta_log/00000000000000000010.checkpoint.parquet"),
+            source: "no such file or directory".into(),
+        };
+        assert!(matches!(
+            TableReaderError::from(not_found),
+            TableReaderError::Other { .. }
+        ));
+        for status in [404, 500] {
+            assert!(matches!(
+                TableReaderError::from(response_error(status)),
+                TableReaderError::Other { .. }
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_table() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage_path = dir.path().join("missing").to_str().unwrap().to_owned();
+        let reader = DeltaTableReader::new();
+
+        for version in [Version::Latest, Version::Number(0)] {
+            assert_eq!(
+                reader.get_table_version(&storage_path, version).await,
+                Err(TableReaderError::TableNotFound {
+                    storage_path: storage_path.clone()
+                })
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn version_at_timestamp_after_log_cleanup() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_dir = dir.path().join("_delta_log");
+        std::fs::create_dir(&log_dir).unwrap();
+        // The commits of versions 0 and 1 have been cleaned up.
+        for version in 2..5 {
+            let commit = json!({"commitInfo": {"timestamp": (version + 1) * 1000}});
+            std::fs::write(
+                log_dir.join(format!("{:020}.json", version)),
+                commit.to_string(),
+            )
+            .unwrap();
+        }
+        let storage_path = dir.path().to_str().unwrap();
+        let reader = DeltaTableReader::new();
+        let timestamp = |millis| Utc.timestamp_millis_opt(millis).unwrap();
+
+        for (millis, version) in [(3000, 2), (4500, 3), (9000, 4)] {
+            assert_eq!(
+                reader
+                    .get_table_version(storage_path, Version::Timestamp(timestamp(millis)))
+                    .await,
+                Ok(version)
+            );
+        }
+        for millis in [1500, 2999] {
+            assert_eq!(
+                reader
+                    .get_table_version(storage_path, Version::Timestamp(timestamp(millis)))
+                    .await,
+                Err(TableReaderError::TimestampOutOfRange {
+                    timestamp: timestamp(millis).to_rfc3339()
+                })
+            );
+        }
+
+        let range = VersionRange::Timestamp {
+            start: timestamp(1500),
+            end: timestamp(9000),
+        };
+        assert_eq!(
+            reader
+                .get_table_changes(storage_path, range)
+                .await
+                .unwrap_err(),
+            TableReaderError::TimestampOutOfRange {
+                timestamp: timestamp(1500).to_rfc3339()
+            }
+        );
+    }
+
+    #[test]
+    fn record_count_from_stats() {
+        assert_eq!(num_records(r#"{"numRecords":42,"minValues":{}}"#), Some(42));
+        assert_eq!(num_records(r#"{"minValues":{}}"#), None);
+        assert_eq!(num_records("not json"), None);
+    }
+}
@@ -0,0 +1,268 @@
+//! Cache of Delta table snapshots.
+//!
+//! Replaying the transaction log dominates the latency of table queries. The
+//! cache keeps the most recently used snapshots by table location and
+//! version, such that repeated queries of a table version skip the replay and
+//! queries of a later version only replay the commits after a cached
+//! snapshot. The latest version of a table is remembered for a configurable
+//! staleness window, so polling clients do not list the transaction log on
+//! every request.
+//!
+//! The size of the cache is bounded by the approximate memory held by the
+//! cached snapshots, estimated from the size of their actions in JSON while
+//! the log is replayed, since a single snapshot of a large table can hold
+//! millions of file actions.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use super::log_replay::LogSnapshot;
+
+/// Size bounded cache of table snapshots with least recently used eviction.
+#[derive(Debug)]
+pub(super) struct SnapshotCache {
+    /// Maximum total size of the cached snapshots in bytes.
+    capacity: usize,
+    staleness: Duration,
+    state: Mutex<CacheState>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    /// Snapshots by table location and version, with their size and the
+    /// tick of their last use.
+    snapshots: HashMap<(String, u64), CachedSnapshot>,
+    /// Total size of the cached snapshots.
+    size: usize,
+    /// Latest version of a table by location and the moment it was looked
+    /// up. Entries older than the staleness window are evicted.
+    latest_versions: HashMap<String, (u64, Instant)>,
+    tick: u64,
+}
+
+#[derive(Debug)]
+struct CachedSnapshot {
+    snapshot: Arc<LogSnapshot>,
+    size: usize,
+    last_used: u64,
+}
+
+impl CacheState {
+    fn touch(&mut self, key: &(String, u64)) -> Option<Arc<LogSnapshot>> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.snapshots.get_mut(key).map(|cached| {
+            cached.last_used = tick;
+            cached.snapshot.clone()
+        })
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        let least_recently_used = self
+            .snapshots
+            .iter()
+            .min_by_key(|(_, cached)| cached.last_used)
+            .map(|(key, _)| key.clone());
+        if let Some(cached) = least_recently_used.and_then(|key| self.snapshots.remove(&key)) {
+            self.size -= cached.size;
+        }
+    }
+}
+
+impl SnapshotCache {
+    /// Create a cache that holds snapshots up to a total size of `capacity`
+    /// bytes and trusts the latest version of a table for the `staleness`
+    /// window.
+    pub fn new(capacity: usize, staleness: Duration) -> Self {
+        Self {
+            capacity,
+            staleness,
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, CacheState> {
+        // The state is consistent after every statement, so it can be used
+        // even if another thread panicked while holding the lock.
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Retrieve the latest version of the table at the location, if it was
+    /// looked up within the staleness window.
+    pub fn latest_version(&self, location: &str) -> Option<u64> {
+        self.state()
+            .latest_versions
+            .get(location)
+            .filter(|(_, looked_up_at)| looked_up_at.elapsed() < self.staleness)
+            .map(|(version, _)| *version)
+    }
+
+    /// Remember the latest version of the table at the location, and forget
+    /// the latest versions that are no longer within the staleness window.
+    pub fn set_latest_version(&self, location: &str, version: u64) {
+        if self.staleness.is_zero() {
+            return;
+        }
+
+        let mut state = self.state();
+        state
+            .latest_versions
+            .retain(|_, (_, looked_up_at)| looked_up_at.elapsed() < self.staleness);
+        state
+            .latest_versions
+            .insert(location.to_owned(), (version, Instant::now()));
+    }
+
+    /// Retrieve the snapshot of the table at the location in a version.
+    pub fn get(&self, location: &str, version: u64) -> Option<Arc<LogSnapshot>> {
+        self.state().touch(&(location.to_owned(), version))
+    }
+
+    /// Retrieve the most recent snapshot of the table at the location before
+    /// a version, from which the snapshot in that version can be derived.
+    pub fn latest_before(&self, location: &str, version: u64) -> Option<Arc<LogSnapshot>> {
+        let mut state = self.state();
+        let key = state
+            .snapshots
+            .keys()
+            .filter(|(l, v)| l == location && *v < version)
+            .max_by_key(|(_, v)| *v)
+            .cloned()?;
+        state.touch(&key)
+    }
+
+    /// Add a snapshot of the table at the location to the cache, evicting the
+    /// least recently used snapshots when the cache is full. A snapshot that
+    /// is larger than the cache is not cached.
+    pub fn insert(&self, location: &str, snapshot: Arc<LogSnapshot>) {
+        let size = snapshot.size;
+        if size > self.capacity {
+            return;
+        }
+
+        let mut state = self.state();
+        state.tick += 1;
+        let cached = CachedSnapshot {
+            snapshot: snapshot.clone(),
+            size,
+            last_used: state.tick,
+        };
+        let key = (location.to_owned(), snapshot.version);
+        if let Some(replaced) = state.snapshots.insert(key, cached) {
+            state.size -= replaced.size;
+        }
+        state.size += size;
+        while state.size > self.capacity {
+            state.evict_least_recently_used();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::reader::delta::log_replay::json_size;
+
+    fn snapshot(version: u64) -> Arc<LogSnapshot> {
+        snapshot_with_files(version, vec![])
+    }
+
+    fn snapshot_with_files(version: u64, files: Vec<serde_json::Value>) -> Arc<LogSnapshot> {
+        let protocol = json!({"minReaderVersion": 1, "minWriterVersion": 2});
+        let metadata = json!({"id": "a"});
+        let size = [&protocol, &metadata]
+            .into_iter()
+            .chain(&files)
+            .map(json_size)
+            .sum();
+        Arc::new(LogSnapshot {
+            version,
+            protocol,
+            metadata,
+            files,
+            size,
+        })
+    }
+
+    #[test]
+    fn evict_least_recently_used_snapshot() {
+        let size = snapshot(1).size;
+        let cache = SnapshotCache::new(2 * size, Duration::ZERO);
+        cache.insert("s3://bucket/a", snapshot(1));
+        cache.insert("s3://bucket/a", snapshot(2));
+        assert!(cache.get("s3://bucket/a", 1).is_some());
+
+        cache.insert("s3://bucket/b", snapshot(1));
+        assert!(cache.get("s3://bucket/a", 1).is_some());
+        assert!(cache.get("s3://bucket/a", 2).is_none());
+        assert!(cache.get("s3://bucket/b", 1).is_some());
+    }
+
+    #[test]
+    fn evict_snapshots_by_size() {
+        let large = snapshot_with_files(2, vec![json!({"path": "a.parquet"}); 10]);
+        let size = large.size;
+        assert!(2 * snapshot(1).size <= size);
+
+        let cache = SnapshotCache::new(size, Duration::ZERO);
+        cache.insert("s3://bucket/a", snapshot(1));
+        cache.insert("s3://bucket/b", snapshot(1));
+        cache.insert("s3://bucket/a", large);
+        assert!(cache.get("s3://bucket/a", 2).is_some());
+        assert!(cache.get("s3://bucket/a", 1).is_none());
+        assert!(cache.get("s3://bucket/b", 1).is_none());
+
+        // Snapshots that do not fit are not cached at all.
+        let larger = snapshot_with_files(3, vec![json!({"path": "a.parquet"}); 11]);
+        cache.insert("s3://bucket/a", larger);
+        assert!(cache.get("s3://bucket/a", 3).is_none());
+        assert!(cache.get("s3://bucket/a", 2).is_some());
+    }
+
+    #[test]
+    fn find_snapshot_before_version() {
+        let cache = SnapshotCache::new(4 * snapshot(1).size, Duration::ZERO);
+        cache.insert("s3://bucket/a", snapshot(1));
+        cache.insert("s3://bucket/a", snapshot(3));
+        cache.insert("s3://bucket/b", snapshot(4));
+
+        assert_eq!(cache.latest_before("s3://bucket/a", 5).unwrap().version, 3);
+        assert_eq!(cache.latest_before("s3://bucket/a", 3).unwrap().version, 1);
+        assert!(cache.latest_before("s3://bucket/a", 1).is_none());
+    }
+
+    #[test]
+    fn disabled_cache() {
+        let cache = SnapshotCache::new(0, Duration::ZERO);
+        cache.insert("s3://bucket/a", snapshot(1));
+        assert!(cache.get("s3://bucket/a", 1).is_none());
+
+        cache.set_latest_version("s3://bucket/a", 1);
+        assert_eq!(cache.latest_version("s3://bucket/a"), None);
+        assert!(cache.state().latest_versions.is_empty());
+    }
+
+    #[test]
+    fn latest_version_within_staleness_window() {
+        let cache = SnapshotCache::new(0, Duration::from_secs(60));
+        assert_eq!(cache.latest_version("s3://bucket/a"), None);
+        cache.set_latest_version("s3://bucket/a", 7);
+        assert_eq!(cache.latest_version("s3://bucket/a"), Some(7));
+    }
+
+    #[test]
+    fn evict_stale_latest_versions() {
+        let cache = SnapshotCache::new(0, Duration::from_millis(20));
+        cache.set_latest_version("s3://bucket/a", 7);
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(cache.latest_version("s3://bucket/a"), None);
+
+        cache.set_latest_version("s3://bucket/b", 3);
+        let state = cache.state();
+        assert_eq!(state.latest_versions.len(), 1);
+        assert!(state.latest_versions.contains_key("s3://bucket/b"));
+    }
+}
@@ -1,11 +1,20 @@
 //! Types and traits for reading table data in object storage.
 
-use std::{error::Error, fmt::Display};
+use std::{
+    error::Error,
+    fmt::{Debug, Display},
+};
 
 use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
 
-use crate::protocol::table::{
-    TableMetadata, TableVersionNumber, UnsignedTableData, Version, VersionRange,
+use crate::protocol::{
+    action::{Metadata, Protocol},
+    predicate::Predicate,
+    table::{
+        TableMetadata, TableVersionNumber, UnsignedDataFile, UnsignedTableData, Version,
+        VersionRange,
+    },
 };
 
 /// Table reader implementation for the Delta Lake format.
@@ -34,13 +43,47 @@ pub trait TableReader: Send + Sync {
     /// The table data is represented by a collection of files which can be
     /// directly reached with a presigned HTTPS url. The limit and predicate
     /// argument can be used to restrict the returned data files on a best
-    /// effort basis.
+    /// effort basis. Data files may only be skipped when they certainly
+    /// contain no rows that satisfy the predicate.
     async fn get_table_data(
         &self,
         storage_path: &str,
         version: u64,
         limit: Option<u64>,
-        predicates: Option<String>,
+        predicate: Option<Predicate>,
+    ) -> Result<UnsignedTableData, TableReaderError>;
+
+    /// Stream the table data for a specific table version.
+    ///
+    /// The protocol and metadata are returned up front, while the data files
+    /// are produced one by one. This allows the server to respond before all
+    /// data files of a large table are known. By default the data files of
+    /// [`TableReader::get_table_data`] are streamed.
+    async fn get_table_data_stream(
+        &self,
+        storage_path: &str,
+        version: u64,
+        limit: Option<u64>,
+        predicate: Option<Predicate>,
+    ) -> Result<UnsignedTableDataStream, TableReaderError> {
+        let table_data = self
+            .get_table_data(storage_path, version, limit, predicate)
+            .await?;
+        Ok(table_data.into())
+    }
+
+    /// Retrieve the data files added to the table in a range of table
+    /// versions.
+    ///
+    /// Every data file is tagged with the table version in which it was added
+    /// and the timestamp of that version. Data files that were added without
+    /// changing the data of the table are left out. This is used by clients
+    /// that stream the changes of a table.
+    async fn get_table_data_since(
+        &self,
+        storage_path: &str,
+        starting_version: u64,
+        ending_version: u64,
     ) -> Result<UnsignedTableData, TableReaderError>;
 
     /// Retrieve the table change data for a specific range of table versions.
@@ -54,18 +97,141 @@ pub trait TableReader: Send + Sync {
     ) -> Result<UnsignedTableData, TableReaderError>;
 }
 
+/// Stream of data files that are not yet publicly accessible.
+pub type UnsignedDataFileStream = BoxStream<'static, Result<UnsignedDataFile, TableReaderError>>;
+
+/// Table metadata and a stream of data descriptors, not yet publicly
+/// accessible.
+pub struct UnsignedTableDataStream {
+    /// Table version.
+    pub version: u64,
+    /// Minimum required table reader protocol implementation.
+    pub protocol: Protocol,
+    /// Table metadata
+    pub metadata: Metadata,
+    /// Stream of data files representing the table
+    pub data: UnsignedDataFileStream,
+}
+
+impl UnsignedTableDataStream {
+    /// Wait for all data files and collect them into the table data.
+    pub async fn collect(self) -> Result<UnsignedTableData, TableReaderError> {
+        Ok(UnsignedTableData {
+            version: self.version,
+            protocol: self.protocol,
+            metadata: self.metadata,
+            data: self.data.try_collect().await?,
+        })
+    }
+}
+
+impl Debug for UnsignedTableDataStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UnsignedTableDataStream")
+            .field("version", &self.version)
+            .field("protocol", &self.protocol)
+            .field("metadata", &self.metadata)
+            .finish_non_exhaustive()
+    }
+}
+
+impl From<UnsignedTableData> for UnsignedTableDataStream {
+    fn from(value: UnsignedTableData) -> Self {
+        Self {
+            version: value.version,
+            protocol: value.protocol,
+            metadata: value.metadata,
+            data: stream::iter(value.data.into_iter().map(Ok)).boxed(),
+        }
+    }
+}
+
 /// Error that occur during the reading of the table format.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TableReaderError {
+    /// No table could be found at the storage path.
+    TableNotFound {
+        /// Location of the table in object storage.
+        storage_path: String,
+    },
+    /// The table version does not exist, or is no longer available in the
+    /// transaction log.
+    VersionNotFound {
+        /// The requested table version.
+        version: u64,
+    },
+    /// The timestamp is outside the history of the table.
+    TimestampOutOfRange {
+        /// The requested timestamp in RFC 3339 format.
+        timestamp: String,
+    },
+    /// The table uses a reader feature that the table reader does not
+    /// support.
+    UnsupportedReaderFeature {
+        /// Name of the reader feature.
+        feature: String,
+    },
+    /// The server is not permitted to read the table from storage.
+    PermissionDenied {
+        /// Error reported by the storage service.
+        reason: String,
+    },
+    /// The storage service rejected the request because too many requests
+    /// were made.
+    Throttled {
+        /// Error reported by the storage service.
+        reason: String,
+    },
+    /// The transaction log of the table cannot be interpreted.
+    CorruptedLog {
+        /// Description of the problem in the transaction log.
+        reason: String,
+    },
     /// An unexpected error occured.
-    Other,
+    Other {
+        /// Description of the cause of the error.
+        reason: String,
+    },
 }
 
 impl Display for TableReaderError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            TableReaderError::Other => {
-                write!(f, "An unexpected error happened during table reading")
+            TableReaderError::TableNotFound { storage_path } => {
+                write!(f, "no table found at `{}`", storage_path)
+            }
+            TableReaderError::VersionNotFound { version } => {
+                write!(f, "version {} of the table does not exist", version)
+            }
+            TableReaderError::TimestampOutOfRange { timestamp } => {
+                write!(
+                    f,
+                    "timestamp `{}` is outside the history of the table",
+                    timestamp
+                )
+            }
+            TableReaderError::UnsupportedReaderFeature { feature } => {
+                write!(f, "the table uses unsupported reader feature `{}`", feature)
+            }
+            TableReaderError::PermissionDenied { reason } => {
+                write!(f, "permission to read the table was denied: {}", reason)
+            }
+            TableReaderError::Throttled { reason } => {
+                write!(f, "the storage service throttled the request: {}", reason)
+            }
+            TableReaderError::CorruptedLog { reason } => {
+                write!(
+                    f,
+                    "the transaction log of the table is corrupted: {}",
+                    reason
+                )
+            }
+            TableReaderError::Other { reason } => {
+                write!(
+                    f,
+                    "An unexpected error happened during table reading: {}",
+                    reason
+                )
             }
         }
     }
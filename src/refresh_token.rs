@@ -0,0 +1,143 @@
+//! Tokens to refresh the pre-signed urls of a table data query.
+//!
+//! A refresh token holds the table, the table version and the predicates of
+//! a table data query. The token is signed by the server with an HMAC, so a
+//! client cannot alter the query it describes. Sending the token back returns
+//! the same data files with freshly signed urls.
+//!
+//! A token records when it was issued and is rejected once it is older than
+//! the refresh token lifetime of the server.
+
+use std::time::Duration;
+
+use base64::{engine::general_purpose, Engine as _};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::error::ServerError;
+
+/// Query that is repeated when the client refreshes the urls of the data
+/// files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct RefreshToken {
+    /// Full name of the table, i.e. `share.schema.table`.
+    pub table: String,
+    /// Table version of the data files.
+    pub version: u64,
+    /// Number of rows the client wanted to read.
+    pub limit: Option<u64>,
+    /// Predicate in the JSON predicate language.
+    pub predicate: Option<String>,
+    /// Time the token was issued, in seconds since the Unix epoch.
+    pub issued_at: i64,
+}
+
+impl RefreshToken {
+    /// Encode and sign the refresh token with the key of the server.
+    pub fn encode(&self, key: &[u8]) -> String {
+        let payload = serde_json::to_vec(self).expect("refresh token can be serialized");
+        let signature = mac(key, &payload).finalize().into_bytes();
+        format!(
+            "{}.{}",
+            general_purpose::URL_SAFE_NO_PAD.encode(payload),
+            general_purpose::URL_SAFE_NO_PAD.encode(signature)
+        )
+    }
+
+    /// Decode the refresh token and verify that it was signed with the key
+    /// of the server and was issued no longer than `lifetime` ago.
+    pub fn decode(token: &str, key: &[u8], lifetime: Duration) -> Result<Self, ServerError> {
+        let invalid_token = || ServerError::InvalidRefreshToken {
+            reason: String::from("the provided `refreshToken` is malformed"),
+        };
+
+        let (payload, signature) = token.split_once('.').ok_or_else(invalid_token)?;
+        let payload = general_purpose::URL_SAFE_NO_PAD
+            .decode(payload)
+            .map_err(|_| invalid_token())?;
+        let signature = general_purpose::URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| invalid_token())?;
+        mac(key, &payload).verify_slice(&signature).map_err(|_| {
+            ServerError::InvalidRefreshToken {
+                reason: String::from("the provided `refreshToken` was not issued by this server"),
+            }
+        })?;
+
+        let token: Self = serde_json::from_slice(&payload).map_err(|_| invalid_token())?;
+        let age = chrono::Utc::now().timestamp() - token.issued_at;
+        if age > lifetime.as_secs() as i64 {
+            return Err(ServerError::InvalidRefreshToken {
+                reason: String::from("the provided `refreshToken` has expired"),
+            });
+        }
+        Ok(token)
+    }
+}
+
+fn mac(key: &[u8], payload: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(payload);
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn refresh_token() -> RefreshToken {
+        RefreshToken {
+            table: String::from("vaccine_share.acme_vaccine_data.vaccine_patients"),
+            version: 3,
+            limit: Some(100),
+            predicate: Some(String::from(
+                r#"{"op":"isNull","children":[{"op":"column","name":"date","valueType":"date"}]}"#,
+            )),
+            issued_at: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    const LIFETIME: Duration = Duration::from_secs(3600);
+
+    #[test]
+    fn encode_and_decode_refresh_token() {
+        let refresh_token = refresh_token();
+        let token = refresh_token.encode(b"secret");
+        assert_eq!(
+            RefreshToken::decode(&token, b"secret", LIFETIME).unwrap(),
+            refresh_token
+        );
+    }
+
+    #[test]
+    fn reject_tampered_refresh_token() {
+        let token = refresh_token().encode(b"secret");
+        assert!(RefreshToken::decode(&token, b"other secret", LIFETIME).is_err());
+
+        let (_, signature) = token.split_once('.').unwrap();
+        let mut tampered = refresh_token();
+        tampered.version = 2;
+        let tampered_payload =
+            general_purpose::URL_SAFE_NO_PAD.encode(serde_json::to_vec(&tampered).unwrap());
+        let tampered_token = format!("{}.{}", tampered_payload, signature);
+        assert!(RefreshToken::decode(&tampered_token, b"secret", LIFETIME).is_err());
+
+        assert!(RefreshToken::decode("not a token", b"secret", LIFETIME).is_err());
+    }
+
+    #[test]
+    fn reject_expired_refresh_token() {
+        let mut refresh_token = refresh_token();
+        refresh_token.issued_at -= 3601;
+        let token = refresh_token.encode(b"secret");
+        assert!(matches!(
+            RefreshToken::decode(&token, b"secret", LIFETIME),
+            Err(ServerError::InvalidRefreshToken { .. })
+        ));
+
+        refresh_token.issued_at += 2;
+        let token = refresh_token.encode(b"secret");
+        assert!(RefreshToken::decode(&token, b"secret", LIFETIME).is_ok());
+    }
+}
@@ -1,5 +1,6 @@
+use std::collections::HashMap;
 use std::error::Error;
-use std::fmt::{Display, Formatter};
+use std::fmt::{Debug, Display, Formatter};
 use std::io::Write;
 
 use axum::body::Body;
@@ -12,10 +13,16 @@ use axum::{
     Json,
 };
 use bytes::{BufMut, BytesMut};
-use futures_util::stream::TryStreamExt;
+use futures::future::BoxFuture;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
 use serde::Serialize;
+use serde_json::{json, Value};
 
-use crate::protocol::action::{Metadata, Protocol};
+use crate::error::ServerError;
+use crate::protocol::action::{
+    Add, Cdf, DeletionVectorFile, DeltaProtocol, File, Metadata, Protocol, Remove,
+};
+use crate::protocol::capabilities::{ResponseCapabilities, ResponseFormat, CAPABILITIES_HEADER};
 use crate::protocol::securable::{Schema, Share, Table};
 use crate::protocol::share::List;
 use crate::protocol::table::{SignedDataFile, SignedTableData, TableMetadata, TableVersionNumber};
@@ -184,14 +191,370 @@ pub enum JsonWrapper {
     Protocol(Protocol),
     #[serde(rename = "metaData")]
     Metadata(Metadata),
-    File(SignedDataFile),
-    // Add(SignedChangeFile),
+    File(File),
+    Add(Add),
+    Cdf(Cdf),
+    Remove(Remove),
+}
+
+impl From<SignedDataFile> for JsonWrapper {
+    fn from(value: SignedDataFile) -> Self {
+        match value {
+            SignedDataFile::File(file) => Self::File(file),
+            SignedDataFile::Add(add) => Self::Add(add),
+            SignedDataFile::Cdf(cdf) => Self::Cdf(cdf),
+            SignedDataFile::Remove(remove) => Self::Remove(remove),
+        }
+    }
+}
+
+/// Action in the Delta response format, which wraps the raw actions of the
+/// Delta transaction log.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DeltaJsonWrapper {
+    Protocol(DeltaProtocolAction),
+    #[serde(rename = "metaData")]
+    Metadata(DeltaMetadataAction),
+    File(DeltaFileAction),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaProtocolAction {
+    delta_protocol: DeltaProtocol,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaMetadataAction {
+    delta_metadata: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_files: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaFileAction {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deletion_vector_file_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expiration_timestamp: Option<i64>,
+    delta_single_action: Value,
+}
+
+impl From<Protocol> for DeltaJsonWrapper {
+    fn from(value: Protocol) -> Self {
+        // Tables that are not read from a Delta transaction log are described
+        // by the lowest Delta protocol that satisfies the reader version.
+        let delta_protocol = value
+            .delta_protocol()
+            .cloned()
+            .unwrap_or_else(|| DeltaProtocol::new(value.min_reader_version(), 2));
+        Self::Protocol(DeltaProtocolAction { delta_protocol })
+    }
+}
+
+impl From<Metadata> for DeltaJsonWrapper {
+    fn from(value: Metadata) -> Self {
+        let delta_metadata = value.delta_metadata().cloned().unwrap_or_else(|| {
+            json!({
+                "id": value.id(),
+                "format": {"provider": value.format().provider, "options": {}},
+                "schemaString": value.schema_string(),
+                "partitionColumns": value.partition_columns(),
+                "configuration": value.configuration(),
+            })
+        });
+        Self::Metadata(DeltaMetadataAction {
+            delta_metadata,
+            version: value.version().and_then(|v| v.parse().ok()),
+            size: value.size(),
+            num_files: value.num_files(),
+        })
+    }
+}
+
+impl From<SignedDataFile> for DeltaJsonWrapper {
+    fn from(value: SignedDataFile) -> Self {
+        let file = match value {
+            SignedDataFile::File(file) => DeltaFileAction {
+                id: file.id().to_owned(),
+                deletion_vector_file_id: file.deletion_vector().map(|dv| dv.id().to_owned()),
+                version: None,
+                timestamp: None,
+                expiration_timestamp: parse_millis(file.expiration_timestamp()),
+                delta_single_action: delta_single_action(
+                    "add",
+                    file.delta_action().cloned(),
+                    file.deletion_vector(),
+                    file.url(),
+                    file.partition_values(),
+                    file.size(),
+                    file.stats(),
+                ),
+            },
+            SignedDataFile::Add(add) => DeltaFileAction {
+                id: add.id,
+                deletion_vector_file_id: add.deletion_vector.as_ref().map(|dv| dv.id().to_owned()),
+                version: Some(add.version),
+                timestamp: parse_millis(Some(&add.timestamp)),
+                expiration_timestamp: parse_millis(add.expiration_timestamp.as_deref()),
+                delta_single_action: delta_single_action(
+                    "add",
+                    add.delta_action,
+                    add.deletion_vector.as_ref(),
+                    &add.url,
+                    &add.partition_values,
+                    add.size,
+                    add.stats.as_deref(),
+                ),
+            },
+            SignedDataFile::Cdf(cdf) => DeltaFileAction {
+                id: cdf.id,
+                deletion_vector_file_id: None,
+                version: Some(cdf.version),
+                timestamp: parse_millis(Some(&cdf.timestamp)),
+                expiration_timestamp: parse_millis(cdf.expiration_timestamp.as_deref()),
+                delta_single_action: delta_single_action(
+                    "cdc",
+                    cdf.delta_action,
+                    None,
+                    &cdf.url,
+                    &cdf.partition_values,
+                    cdf.size,
+                    cdf.stats.as_deref(),
+                ),
+            },
+            SignedDataFile::Remove(remove) => DeltaFileAction {
+                id: remove.id,
+                deletion_vector_file_id: remove
+                    .deletion_vector
+                    .as_ref()
+                    .map(|dv| dv.id().to_owned()),
+                version: Some(remove.version),
+                timestamp: parse_millis(Some(&remove.timestamp)),
+                expiration_timestamp: parse_millis(remove.expiration_timestamp.as_deref()),
+                delta_single_action: delta_single_action(
+                    "remove",
+                    remove.delta_action,
+                    remove.deletion_vector.as_ref(),
+                    &remove.url,
+                    &remove.partition_values,
+                    remove.size,
+                    remove.stats.as_deref(),
+                ),
+            },
+        };
+        Self::File(file)
+    }
+}
+
+fn parse_millis(millis: Option<&str>) -> Option<i64> {
+    millis.and_then(|millis| millis.parse().ok())
+}
+
+/// Wrap the Delta action of a data file, pointing its path and the path of
+/// its deletion vector at the signed urls. Data files without a Delta action
+/// are described by a synthesized action.
+fn delta_single_action(
+    kind: &str,
+    action: Option<Value>,
+    deletion_vector: Option<&DeletionVectorFile>,
+    url: &str,
+    partition_values: &HashMap<String, Option<String>>,
+    size: u64,
+    stats: Option<&str>,
+) -> Value {
+    let mut action = action.unwrap_or_else(|| {
+        let mut action = json!({
+            "partitionValues": partition_values,
+            "size": size,
+            "dataChange": kind != "cdc",
+        });
+        if kind == "add" {
+            action["modificationTime"] = json!(0);
+        }
+        if let Some(stats) = stats {
+            action["stats"] = json!(stats);
+        }
+        action
+    });
+    action["path"] = json!(url);
+    if let (Some(dv), Some(descriptor)) = (deletion_vector, action.get_mut("deletionVector")) {
+        descriptor["storageType"] = json!("p");
+        descriptor["pathOrInlineDv"] = json!(dv.url());
+    }
+    json!({ kind: action })
+}
+
+/// Action that ends a table actions response in both response formats.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EndStreamWrapper {
+    EndStreamAction(EndStreamAction),
+}
+
+/// Last action of a table actions response, which tells the client how to
+/// continue reading the table.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndStreamAction {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_page_token: Option<String>,
+}
+
+impl EndStreamAction {
+    /// Create the action that ends a response. Without a token for the next
+    /// page, the response holds the last page of data files.
+    pub fn new(next_page_token: Option<String>) -> Self {
+        Self {
+            refresh_token: None,
+            next_page_token,
+        }
+    }
+
+    /// Set the token to refresh the urls of the data files in the response.
+    pub fn with_refresh_token(mut self, refresh_token: impl Into<String>) -> Self {
+        self.refresh_token = Some(refresh_token.into());
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum ResponseLine {
+    Parquet(JsonWrapper),
+    Delta(DeltaJsonWrapper),
+    EndStream(EndStreamWrapper),
+}
+
+/// Response to a table query of which the lines are written as NDJSON while
+/// they are produced.
 pub struct TableActionsResponse {
     version: TableVersionNumber,
-    lines: Vec<JsonWrapper>,
+    lines: BoxStream<'static, Result<ResponseLine, ServerError>>,
+    capabilities: ResponseCapabilities,
+}
+
+impl TableActionsResponse {
+    /// Create the response to a table metadata query in the negotiated
+    /// response format.
+    pub fn from_table_metadata(value: TableMetadata, capabilities: ResponseCapabilities) -> Self {
+        let lines = table_header(value.protocol, value.metadata, capabilities.format());
+        Self {
+            version: value.version,
+            lines: stream::iter(lines.into_iter().map(Ok)).boxed(),
+            capabilities,
+        }
+    }
+
+    /// Create the response to a table data or table changes query in the
+    /// negotiated response format.
+    pub fn from_table_data(value: SignedTableData, capabilities: ResponseCapabilities) -> Self {
+        Self::from_table_data_stream(
+            value.version,
+            value.protocol,
+            value.metadata,
+            stream::iter(value.data.into_iter().map(Ok)).boxed(),
+            None,
+            capabilities,
+        )
+    }
+
+    /// Create the response to a table data or table changes query in the
+    /// negotiated response format, of which the data files are written as
+    /// they are produced.
+    ///
+    /// The response ends with the end stream action, if any, once all data
+    /// files have been written.
+    pub fn from_table_data_stream(
+        version: TableVersionNumber,
+        protocol: Protocol,
+        metadata: Metadata,
+        data: BoxStream<'static, Result<SignedDataFile, ServerError>>,
+        end_stream_action: Option<BoxFuture<'static, EndStreamAction>>,
+        capabilities: ResponseCapabilities,
+    ) -> Self {
+        let format = capabilities.format();
+        let header = table_header(protocol, metadata, format);
+        let files = data.map_ok(move |file| match format {
+            ResponseFormat::Parquet => ResponseLine::Parquet(file.into()),
+            ResponseFormat::Delta => ResponseLine::Delta(file.into()),
+        });
+        let end_stream = stream::iter(end_stream_action)
+            .then(|action| action)
+            .map(|action| {
+                Ok(ResponseLine::EndStream(EndStreamWrapper::EndStreamAction(
+                    action,
+                )))
+            });
+
+        Self {
+            version,
+            lines: stream::iter(header.into_iter().map(Ok))
+                .chain(files)
+                .chain(end_stream)
+                .boxed(),
+            capabilities,
+        }
+    }
+
+    /// Produce all lines of the response.
+    #[cfg(test)]
+    pub async fn collect_lines(self) -> Result<CollectedTableActionsResponse, ServerError> {
+        Ok(CollectedTableActionsResponse {
+            version: self.version,
+            lines: self.lines.try_collect().await?,
+        })
+    }
+}
+
+impl Debug for TableActionsResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TableActionsResponse")
+            .field("version", &self.version)
+            .field("capabilities", &self.capabilities)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Table actions response of which all lines have been produced.
+#[cfg(test)]
+#[derive(Debug, Clone, Serialize)]
+pub struct CollectedTableActionsResponse {
+    version: TableVersionNumber,
+    lines: Vec<ResponseLine>,
+}
+
+/// Lines with the protocol and metadata of the table that start a table
+/// actions response.
+fn table_header(
+    protocol: Protocol,
+    metadata: Metadata,
+    format: ResponseFormat,
+) -> Vec<ResponseLine> {
+    match format {
+        ResponseFormat::Parquet => vec![
+            ResponseLine::Parquet(JsonWrapper::Protocol(protocol)),
+            ResponseLine::Parquet(JsonWrapper::Metadata(metadata)),
+        ],
+        ResponseFormat::Delta => vec![
+            ResponseLine::Delta(protocol.into()),
+            ResponseLine::Delta(metadata.into()),
+        ],
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -207,16 +570,20 @@ impl Error for StreamError {}
 
 impl IntoResponse for TableActionsResponse {
     fn into_response(self) -> Response {
-        let raw_stream =
-            futures::stream::iter(self.lines.into_iter().map(Ok::<JsonWrapper, StreamError>));
-        let stream = raw_stream.map_err(Into::into).and_then(|value| async move {
-            let mut buf = BytesMut::new().writer();
-            serde_json::to_writer(&mut buf, &value)?;
-            buf.write_all(b"\n")?;
-            Ok::<_, BoxError>(buf.into_inner().freeze())
-        });
+        // The status of the response has been sent by the time a line fails,
+        // so the failure can only be signaled by aborting the response body.
+        let stream = self
+            .lines
+            .map_err(|_| BoxError::from(StreamError))
+            .and_then(|value| async move {
+                let mut buf = BytesMut::new().writer();
+                serde_json::to_writer(&mut buf, &value)?;
+                buf.write_all(b"\n")?;
+                Ok::<_, BoxError>(buf.into_inner().freeze())
+            });
         let stream = Body::wrap_stream(stream);
         let version = self.version.to_string();
+        let capabilities = self.capabilities.header_value();
 
         let mut headers = HeaderMap::new();
         headers.insert(
@@ -224,6 +591,7 @@ impl IntoResponse for TableActionsResponse {
             "application/x-ndjson; charset=utf-8".parse().unwrap(),
         );
         headers.insert("Delta-Table-Version", version.parse().unwrap());
+        headers.insert(CAPABILITIES_HEADER, capabilities.parse().unwrap());
         let mut response = Response::new(stream);
         *response.headers_mut() = headers;
 
@@ -231,53 +599,14 @@ impl IntoResponse for TableActionsResponse {
     }
 }
 
-// impl IntoResponse for TableActionsResponse {
-//     fn into_response(self) -> Response {
-//         let mut buf = BytesMut::new().writer();
-//         for line in self.lines {
-//             serde_json::to_writer(&mut buf, &line).unwrap();
-//             buf.write_all(b"\n").unwrap();
-//         }
-
-//         let version = self.version.to_string();
-//         let headers = [
-//             (
-//                 header::CONTENT_TYPE.as_str(),
-//                 "application/x-ndjson; charset=utf-8",
-//             ),
-//             ("Delta-Table-Version", version.as_ref()),
-//         ];
-
-//         (StatusCode::OK, headers, buf.into_inner()).into_response()
-//     }
-// }
-
 impl From<TableMetadata> for TableActionsResponse {
     fn from(v: TableMetadata) -> Self {
-        let lines = vec![
-            JsonWrapper::Protocol(v.protocol),
-            JsonWrapper::Metadata(v.metadata),
-        ];
-
-        Self {
-            version: v.version,
-            lines,
-        }
+        Self::from_table_metadata(v, ResponseCapabilities::default())
     }
 }
 
 impl From<SignedTableData> for TableActionsResponse {
     fn from(value: SignedTableData) -> Self {
-        let mut lines = vec![];
-        lines.push(JsonWrapper::Protocol(value.protocol.clone()));
-        lines.push(JsonWrapper::Metadata(value.metadata.clone()));
-        for f in value.data.clone() {
-            lines.push(JsonWrapper::File(f.clone()))
-        }
-
-        Self {
-            version: value.version,
-            lines,
-        }
+        Self::from_table_data(value, ResponseCapabilities::default())
     }
 }
@@ -1,57 +1,311 @@
 //! Traits and types for creating pre-signed urls.
 
+use std::{error::Error, fmt::Display, sync::Arc};
+
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
 
-use crate::protocol::table::{
-    SignedDataFile, SignedTableData, UnsignedDataFile, UnsignedTableData,
+use crate::protocol::{
+    action::DeletionVectorFile,
+    table::{SignedDataFile, SignedTableData, UnsignedDataFile, UnsignedTableData},
 };
 
-mod adls;
-mod gcs;
+pub mod adls;
+pub mod gcs;
+pub mod local;
 pub mod s3;
 
+/// Default number of data files that are signed at the same time.
+pub const DEFAULT_SIGNING_CONCURRENCY: usize = 16;
+
 /// Trait implemented by object store clients to derive a pre-signed url from
 /// a object store path/prefix.
 #[cfg_attr(test, mockall::automock)]
 #[async_trait]
 pub trait UrlSigner: Send + Sync {
     /// Create a presigned url from a object store path.
-    async fn sign_url(&self, path: &str) -> String;
+    async fn sign_url(&self, path: &str) -> Result<SignedUrl, SignerError>;
 
     /// Create a presigned url for a object store path within a data file.
-    async fn sign_data_file(&self, data_file: UnsignedDataFile) -> SignedDataFile {
-        match data_file {
+    ///
+    /// The deletion vector file of the data file, if any, is signed as well.
+    /// The earliest expiration of the presigned urls is recorded in the
+    /// `expirationTimestamp` of the data file.
+    async fn sign_data_file(
+        &self,
+        data_file: UnsignedDataFile,
+    ) -> Result<SignedDataFile, SignerError> {
+        let signed_data_file = match data_file {
             UnsignedDataFile::File(mut file) => {
-                let signed_url = self.sign_url(file.url()).await;
-                *file.url_mut() = signed_url;
+                let mut signed_url = self.sign_url(file.url()).await?;
+                if let Some(dv) = file.deletion_vector_mut() {
+                    signed_url = sign_deletion_vector(self, dv, signed_url).await?;
+                }
+                *file.expiration_timestamp_mut() = signed_url.expiration_timestamp();
+                *file.url_mut() = signed_url.into_url();
                 SignedDataFile::File(file)
             }
             UnsignedDataFile::Add(mut add) => {
-                add.url = self.sign_url(&add.url).await;
+                let mut signed_url = self.sign_url(&add.url).await?;
+                if let Some(dv) = add.deletion_vector.as_mut() {
+                    signed_url = sign_deletion_vector(self, dv, signed_url).await?;
+                }
+                add.expiration_timestamp = signed_url.expiration_timestamp();
+                add.url = signed_url.into_url();
                 SignedDataFile::Add(add)
             }
             UnsignedDataFile::Cdf(mut cdf) => {
-                cdf.url = self.sign_url(&cdf.url).await;
+                let signed_url = self.sign_url(&cdf.url).await?;
+                cdf.expiration_timestamp = signed_url.expiration_timestamp();
+                cdf.url = signed_url.into_url();
                 SignedDataFile::Cdf(cdf)
             }
             UnsignedDataFile::Remove(mut remove) => {
-                remove.url = self.sign_url(&remove.url).await;
+                let mut signed_url = self.sign_url(&remove.url).await?;
+                if let Some(dv) = remove.deletion_vector.as_mut() {
+                    signed_url = sign_deletion_vector(self, dv, signed_url).await?;
+                }
+                remove.expiration_timestamp = signed_url.expiration_timestamp();
+                remove.url = signed_url.into_url();
                 SignedDataFile::Remove(remove)
             }
-        }
+        };
+        Ok(signed_data_file)
     }
 
     /// Create presigned urls for all data files in a table version.
-    async fn sign_table_data(&self, table_data: UnsignedTableData) -> SignedTableData {
-        let mut signed_data_files = vec![];
-        for data_file in table_data.data {
-            signed_data_files.push(self.sign_data_file(data_file).await);
-        }
-        SignedTableData {
+    ///
+    /// Up to [`DEFAULT_SIGNING_CONCURRENCY`] data files are signed at the same
+    /// time. The order of the data files is preserved.
+    async fn sign_table_data(
+        &self,
+        table_data: UnsignedTableData,
+    ) -> Result<SignedTableData, SignerError> {
+        let signed_data_files: Vec<_> = stream::iter(table_data.data)
+            .map(|data_file| self.sign_data_file(data_file))
+            .buffered(DEFAULT_SIGNING_CONCURRENCY)
+            .try_collect()
+            .await?;
+        Ok(SignedTableData {
             version: table_data.version,
             protocol: table_data.protocol,
             metadata: table_data.metadata,
             data: signed_data_files,
+        })
+    }
+}
+
+/// Create presigned urls for a stream of data files.
+///
+/// Up to `concurrency` data files are signed at the same time, while the order
+/// of the data files is preserved. Errors in the stream of unsigned data files
+/// are passed on, and signing errors are converted into the same error type.
+pub fn sign_data_file_stream<E>(
+    signer: Arc<dyn UrlSigner>,
+    data_files: BoxStream<'static, Result<UnsignedDataFile, E>>,
+    concurrency: usize,
+) -> BoxStream<'static, Result<SignedDataFile, E>>
+where
+    E: From<SignerError> + Send + 'static,
+{
+    data_files
+        .map(move |data_file| {
+            let signer = signer.clone();
+            async move {
+                let signed_data_file = signer.sign_data_file(data_file?).await?;
+                Ok::<_, E>(signed_data_file)
+            }
+        })
+        .buffered(concurrency.max(1))
+        .boxed()
+}
+
+/// Sign the url of a deletion vector file in place and return the signed url
+/// of its data file with the earliest expiration of both urls.
+async fn sign_deletion_vector<S: UrlSigner + ?Sized>(
+    signer: &S,
+    deletion_vector: &mut DeletionVectorFile,
+    data_file_url: SignedUrl,
+) -> Result<SignedUrl, SignerError> {
+    let signed_dv_url = signer.sign_url(deletion_vector.url()).await?;
+    let expiration = match (data_file_url.expiration(), signed_dv_url.expiration()) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    };
+    *deletion_vector.url_mut() = signed_dv_url.into_url();
+    Ok(SignedUrl {
+        url: data_file_url.url,
+        expiration,
+    })
+}
+
+/// A presigned url and the moment it stops granting access.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedUrl {
+    url: String,
+    expiration: Option<DateTime<Utc>>,
+}
+
+impl SignedUrl {
+    /// Create a new `SignedUrl` that expires at the given moment.
+    pub fn new(url: impl Into<String>, expiration: DateTime<Utc>) -> Self {
+        Self {
+            url: url.into(),
+            expiration: Some(expiration),
         }
     }
+
+    /// Create a new `SignedUrl` that does not expire.
+    pub fn unexpiring(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            expiration: None,
+        }
+    }
+
+    /// The presigned url.
+    pub fn url(&self) -> &str {
+        self.url.as_ref()
+    }
+
+    /// The moment the presigned url expires, if it expires at all.
+    pub fn expiration(&self) -> Option<DateTime<Utc>> {
+        self.expiration
+    }
+
+    /// The expiration of the presigned url as a unix timestamp in
+    /// milliseconds, formatted like the `expirationTimestamp` of a data file.
+    pub fn expiration_timestamp(&self) -> Option<String> {
+        self.expiration
+            .map(|expiration| expiration.timestamp_millis().to_string())
+    }
+
+    /// Consume the `SignedUrl` and return the presigned url.
+    pub fn into_url(self) -> String {
+        self.url
+    }
+}
+
+/// Errors that can occur during the signing of an object store url.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignerError {
+    /// The object store path could not be interpreted by the signer.
+    InvalidPath {
+        /// The path that could not be signed.
+        path: String,
+    },
+    /// The credentials of the signer could not be used to sign urls.
+    InvalidCredentials {
+        /// Reason why the credentials are invalid.
+        reason: String,
+    },
+    /// Other error
+    Other {
+        /// Reason why this error occurred.
+        reason: String,
+    },
+}
+
+impl Display for SignerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignerError::InvalidPath { path } => {
+                write!(f, "the object store path `{}` cannot be signed", path)
+            }
+            SignerError::InvalidCredentials { reason } => {
+                write!(f, "the signing credentials are invalid: {}", reason)
+            }
+            SignerError::Other { reason } => write!(f, "the url could not be signed: {}", reason),
+        }
+    }
+}
+
+impl Error for SignerError {}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::protocol::action::{AddBuilder, FileBuilder};
+
+    struct FixedExpirationSigner;
+
+    #[async_trait]
+    impl UrlSigner for FixedExpirationSigner {
+        async fn sign_url(&self, path: &str) -> Result<SignedUrl, SignerError> {
+            if path.is_empty() {
+                return Err(SignerError::InvalidPath {
+                    path: path.to_owned(),
+                });
+            }
+            let expiration = Utc.with_ymd_and_hms(2022, 5, 10, 0, 0, 0).unwrap();
+            Ok(SignedUrl::new(
+                format!("{}?signature=123", path),
+                expiration,
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn sign_data_file_sets_expiration_timestamp() {
+        let signer = FixedExpirationSigner;
+
+        let file = FileBuilder::new("s3://bucket/table/file1", "id1").build();
+        match signer.sign_data_file(file.into()).await.unwrap() {
+            SignedDataFile::File(file) => {
+                assert_eq!(file.url(), "s3://bucket/table/file1?signature=123");
+                assert_eq!(file.expiration_timestamp(), Some("1652140800000"));
+            }
+            other => panic!("unexpected data file: {:?}", other),
+        }
+
+        let add = AddBuilder::new("s3://bucket/table/file2", "id2").build();
+        match signer.sign_data_file(add.into()).await.unwrap() {
+            SignedDataFile::Add(add) => {
+                assert_eq!(add.url, "s3://bucket/table/file2?signature=123");
+                assert_eq!(add.expiration_timestamp.as_deref(), Some("1652140800000"));
+            }
+            other => panic!("unexpected data file: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn sign_data_file_with_deletion_vector() {
+        let file = FileBuilder::new("s3://bucket/table/file1", "id1")
+            .deletion_vector("dv1", "s3://bucket/table/deletion_vector_1.bin")
+            .build();
+        match FixedExpirationSigner
+            .sign_data_file(file.into())
+            .await
+            .unwrap()
+        {
+            SignedDataFile::File(file) => {
+                assert_eq!(file.url(), "s3://bucket/table/file1?signature=123");
+                let dv = file.deletion_vector().unwrap();
+                assert_eq!(dv.id(), "dv1");
+                assert_eq!(
+                    dv.url(),
+                    "s3://bucket/table/deletion_vector_1.bin?signature=123"
+                );
+                assert_eq!(file.expiration_timestamp(), Some("1652140800000"));
+            }
+            other => panic!("unexpected data file: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn sign_data_file_propagates_errors() {
+        let file = FileBuilder::new("", "id1").build();
+        assert_eq!(
+            FixedExpirationSigner
+                .sign_data_file(file.into())
+                .await
+                .unwrap_err(),
+            SignerError::InvalidPath {
+                path: String::new()
+            }
+        );
+    }
 }
@@ -1,17 +1,40 @@
 //! Delta Sharing server state.
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use base64::{engine::general_purpose, Engine as _};
+use futures::{
+    future::{self, BoxFuture, FutureExt},
+    stream::{self, BoxStream, StreamExt, TryStreamExt},
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use crate::{
+    auth::{Authenticator, RecipientId},
     error::ServerError,
     manager::ShareReader,
-    protocol::{share::ListCursor, table::Version},
-    reader::TableReader,
+    protocol::{
+        capabilities::{Capabilities, ResponseCapabilities},
+        predicate::Predicate,
+        securable::Table,
+        share::{List, ListCursor},
+        table::{TableChangesQuery, TableDataQuery, TableDataVersion, Version, VersionRange},
+    },
+    reader::{TableReader, UnsignedTableDataStream},
+    refresh_token::RefreshToken,
     response::{
-        GetShareResponse, ListSchemasResponse, ListSharesResponse, ListTablesResponse,
-        TableActionsResponse, TableVersionResponse,
+        EndStreamAction, GetShareResponse, ListSchemasResponse, ListSharesResponse,
+        ListTablesResponse, TableActionsResponse, TableVersionResponse,
     },
-    signer::UrlSigner,
+    signer::{sign_data_file_stream, UrlSigner, DEFAULT_SIGNING_CONCURRENCY},
 };
 
 /// State of the sharing server.
@@ -20,8 +43,31 @@ pub struct SharingServerState {
     shared_table_manager: Arc<dyn ShareReader>,
     table_readers: HashMap<String, Arc<dyn TableReader>>,
     url_signers: HashMap<String, Arc<dyn UrlSigner>>,
+    storage_aliases: HashMap<String, String>,
+    authenticator: Option<Arc<dyn Authenticator>>,
+    refresh_token_key: Vec<u8>,
+    refresh_token_lifetime: Duration,
+    signing_concurrency: usize,
 }
 
+/// Default time during which an issued refresh token is accepted.
+const DEFAULT_REFRESH_TOKEN_LIFETIME: Duration = Duration::from_secs(60 * 60);
+
+/// Default aliases that map the url scheme of a table location onto the name
+/// of the storage for which a url signer is registered.
+const DEFAULT_STORAGE_ALIASES: [(&str, &str); 10] = [
+    ("s3a", "s3"),
+    ("s3n", "s3"),
+    ("gs", "gcs"),
+    ("abfs", "azure"),
+    ("abfss", "azure"),
+    ("wasb", "azure"),
+    ("wasbs", "azure"),
+    ("adl", "azure"),
+    ("az", "azure"),
+    ("azure", "azure"),
+];
+
 impl SharingServerState {
     /// Create a new sharing server state.
     pub fn new(manager: Arc<dyn ShareReader>) -> Self {
@@ -29,6 +75,17 @@ impl SharingServerState {
             shared_table_manager: manager,
             table_readers: HashMap::new(),
             url_signers: HashMap::new(),
+            storage_aliases: DEFAULT_STORAGE_ALIASES
+                .iter()
+                .map(|(alias, storage)| (alias.to_string(), storage.to_string()))
+                .collect(),
+            authenticator: None,
+            refresh_token_key: [Uuid::new_v4(), Uuid::new_v4()]
+                .iter()
+                .flat_map(|uuid| uuid.into_bytes())
+                .collect(),
+            refresh_token_lifetime: DEFAULT_REFRESH_TOKEN_LIFETIME,
+            signing_concurrency: DEFAULT_SIGNING_CONCURRENCY,
         }
     }
 
@@ -38,8 +95,54 @@ impl SharingServerState {
     }
 
     /// Add a url signer to the state.
+    ///
+    /// The signer is used for tables whose location has a url scheme equal to
+    /// `storage` or to one of its aliases. Storage names are case insensitive.
+    /// By default `s3a` and `s3n` are aliases of `s3`, `gs` is an alias of
+    /// `gcs` and `abfs(s)`, `wasb(s)`, `adl` and `az` are aliases of `azure`.
     pub fn add_url_signer(&mut self, storage: impl Into<String>, signer: Arc<dyn UrlSigner>) {
-        self.url_signers.insert(storage.into(), signer);
+        self.url_signers
+            .insert(storage.into().to_ascii_lowercase(), signer);
+    }
+
+    /// Add an alias for a storage, such that tables with a location using
+    /// the `alias` url scheme are signed with the signer of `storage`.
+    pub fn add_storage_alias(&mut self, alias: impl Into<String>, storage: impl Into<String>) {
+        self.storage_aliases.insert(
+            alias.into().to_ascii_lowercase(),
+            storage.into().to_ascii_lowercase(),
+        );
+    }
+
+    /// Set the authenticator used to identify the recipient of a request.
+    ///
+    /// When no authenticator is set, all requests are served anonymously.
+    pub fn set_authenticator(&mut self, authenticator: Arc<dyn Authenticator>) {
+        self.authenticator = Some(authenticator);
+    }
+
+    /// Set the key that signs the refresh tokens of table data queries.
+    ///
+    /// By default a random key is used, so refresh tokens are only accepted
+    /// by the server that issued them and only until it restarts. Servers
+    /// that share the key accept each others refresh tokens.
+    pub fn set_refresh_token_key(&mut self, key: impl Into<Vec<u8>>) {
+        self.refresh_token_key = key.into();
+    }
+
+    /// Set the time during which an issued refresh token is accepted.
+    ///
+    /// Refreshing the urls of a query issues a new refresh token, so clients
+    /// that keep refreshing can keep reading the same table version. The
+    /// default lifetime is one hour.
+    pub fn set_refresh_token_lifetime(&mut self, lifetime: Duration) {
+        self.refresh_token_lifetime = lifetime;
+    }
+
+    /// Set the number of data files that are signed at the same time while
+    /// a table query response is produced.
+    pub fn set_signing_concurrency(&mut self, concurrency: usize) {
+        self.signing_concurrency = concurrency;
     }
 
     /// Set the table readers.
@@ -49,7 +152,10 @@ impl SharingServerState {
 
     /// Set the url signers.
     pub fn set_url_signers(&mut self, signers: HashMap<String, Arc<dyn UrlSigner>>) {
-        self.url_signers = signers;
+        self.url_signers = signers
+            .into_iter()
+            .map(|(storage, signer)| (storage.to_ascii_lowercase(), signer))
+            .collect();
     }
 
     /// Get the share rearder.
@@ -57,6 +163,11 @@ impl SharingServerState {
         self.shared_table_manager.clone()
     }
 
+    /// Get the authenticator.
+    pub fn authenticator(&self) -> Option<Arc<dyn Authenticator>> {
+        self.authenticator.clone()
+    }
+
     /// Get the table reader for a specific format.
     pub fn table_reader(&self, format: &str) -> Option<Arc<dyn TableReader>> {
         self.table_readers.get(format).cloned()
@@ -64,20 +175,80 @@ impl SharingServerState {
 
     /// Get the url signer for a specific object store.
     pub fn url_signer(&self, storage: &str) -> Option<Arc<dyn UrlSigner>> {
-        self.url_signers.get(storage).cloned()
+        let storage = storage.to_ascii_lowercase();
+        self.url_signers.get(&storage).cloned().or_else(|| {
+            self.storage_aliases
+                .get(&storage)
+                .and_then(|storage| self.url_signers.get(storage))
+                .cloned()
+        })
+    }
+
+    /// Get the url signer for the object store of a table location. The
+    /// signer is selected using the url scheme of the location. Locations
+    /// without a scheme are considered to be `file` locations.
+    pub fn url_signer_for_location(
+        &self,
+        location: &str,
+    ) -> Result<Arc<dyn UrlSigner>, ServerError> {
+        let scheme = location
+            .split_once("://")
+            .map(|(scheme, _)| scheme)
+            .unwrap_or("file");
+        self.url_signer(scheme)
+            .ok_or_else(|| ServerError::UnsupportedTableStorage {
+                storage: scheme.to_ascii_lowercase(),
+            })
     }
 
-    /// Get a list of shares in the share store.
+    /// Get a list of shares in the share store that are granted to the
+    /// recipient.
     pub async fn list_shares(
         &self,
+        recipient: &RecipientId,
         cursor: &ListCursor,
     ) -> Result<ListSharesResponse, ServerError> {
-        let shares = self.shared_table_manager.list_shares(cursor).await?;
-        Ok(shares.into())
+        let mut page = self.shared_table_manager.list_shares(cursor).await?;
+        let grants = match self.share_grants(recipient).await? {
+            Some(grants) => grants,
+            None => return Ok(page.into()),
+        };
+
+        // Shares that are not granted are left out, so pages are fetched
+        // until the page is full or the shares run out.
+        let max_results = cursor.max_results();
+        let mut shares = vec![];
+        loop {
+            let page_token = page.next_page_token().cloned();
+            shares.extend(
+                page.iter()
+                    .filter(|share| grants.iter().any(|grant| grant == share.name()))
+                    .cloned(),
+            );
+
+            let page_full = match max_results {
+                Some(max) => shares.len() >= max as usize,
+                None => !shares.is_empty(),
+            };
+            if page_full || page_token.is_none() {
+                return Ok(List::new(shares, page_token).into());
+            }
+
+            let remaining = max_results.map(|max| max - shares.len() as u32);
+            page = self
+                .shared_table_manager
+                .list_shares(&ListCursor::new(remaining, page_token))
+                .await?;
+        }
     }
 
     /// Get a share from the share store.
-    pub async fn get_share(&self, share_name: &str) -> Result<GetShareResponse, ServerError> {
+    pub async fn get_share(
+        &self,
+        recipient: &RecipientId,
+        share_name: &str,
+    ) -> Result<GetShareResponse, ServerError> {
+        self.authorize_share(recipient, share_name).await?;
         let share = self.shared_table_manager.get_share(share_name).await?;
         Ok(share.into())
     }
@@ -85,9 +256,11 @@ impl SharingServerState {
     /// Get a list of schemas in a share.
     pub async fn list_schemas(
         &self,
+        recipient: &RecipientId,
         share_name: &str,
         cursor: &ListCursor,
     ) -> Result<ListSchemasResponse, ServerError> {
+        self.authorize_share(recipient, share_name).await?;
         let schemas = self
             .shared_table_manager
             .list_schemas(share_name, cursor)
@@ -98,9 +271,11 @@ impl SharingServerState {
     /// Get a list of tables in a share.
     pub async fn list_tables_in_share(
         &self,
+        recipient: &RecipientId,
         share_name: &str,
         cursor: &ListCursor,
     ) -> Result<ListTablesResponse, ServerError> {
+        self.authorize_share(recipient, share_name).await?;
         let tables = self
             .shared_table_manager
             .list_tables_in_share(share_name, cursor)
@@ -111,10 +286,12 @@ impl SharingServerState {
     /// Get a list of tables in a schema.
     pub async fn list_tables_in_schema(
         &self,
+        recipient: &RecipientId,
         share_name: &str,
         schema_name: &str,
         cursor: &ListCursor,
     ) -> Result<ListTablesResponse, ServerError> {
+        self.authorize_share(recipient, share_name).await?;
         let tables = self
             .shared_table_manager
             .list_tables_in_schema(share_name, schema_name, cursor)
@@ -125,15 +302,20 @@ impl SharingServerState {
     /// Get the version of a table.
     pub async fn get_table_version(
         &self,
+        recipient: &RecipientId,
         share_name: &str,
         schema_name: &str,
         table_name: &str,
         version: Version,
     ) -> Result<TableVersionResponse, ServerError> {
+        self.authorize_share(recipient, share_name).await?;
         let table = self
             .shared_table_manager
             .get_table(share_name, schema_name, table_name)
             .await?;
+        if version != Version::Latest {
+            ensure_history_shared(&table)?;
+        }
         let table_version = self
             .table_reader(table.format())
             .ok_or_else(|| ServerError::UnsupportedTableFormat {
@@ -141,17 +323,23 @@ impl SharingServerState {
             })?
             .get_table_version(table.storage_path(), version)
             .await?;
+        ensure_version_shared(&table, table_version)?;
 
         Ok(table_version.into())
     }
 
     /// Get the metadata of a table.
+    ///
+    /// The response format is negotiated with the capabilities of the client.
     pub async fn get_table_metadata(
         &self,
+        recipient: &RecipientId,
         share_name: &str,
         schema_name: &str,
         table_name: &str,
+        capabilities: &Capabilities,
     ) -> Result<TableActionsResponse, ServerError> {
+        self.authorize_share(recipient, share_name).await?;
         let table = self
             .table_manager()
             .get_table(share_name, schema_name, table_name)
@@ -165,38 +353,416 @@ impl SharingServerState {
             .get_table_metadata(table.storage_path())
             .await?;
 
-        Ok(table_metadata.into())
+        let response_capabilities = capabilities.negotiate(&table_metadata.protocol)?;
+        Ok(TableActionsResponse::from_table_metadata(
+            table_metadata,
+            response_capabilities,
+        ))
     }
 
-    /// Get the data files of a table version.
+    /// Get the data files of a table version, or the data files added in a
+    /// range of table versions.
+    ///
+    /// When a predicate is given, data files that certainly contain no
+    /// matching rows may be left out of the response. When a limit is given,
+    /// the response may be restricted to the data files that are needed to
+    /// return that many rows. When the client paginates, the response is
+    /// restricted to a page of data files and the table version is pinned by
+    /// the page token. A refresh token repeats an earlier query of a table
+    /// version, such that the same data files are signed again. The response
+    /// format is negotiated with the capabilities of the client.
     pub async fn get_table_data(
         &self,
+        recipient: &RecipientId,
         share_name: &str,
         schema_name: &str,
         table_name: &str,
-        _version: Version,
+        query: TableDataQuery,
+        capabilities: &Capabilities,
     ) -> Result<TableActionsResponse, ServerError> {
+        self.authorize_share(recipient, share_name).await?;
         let table = self
             .table_manager()
             .get_table(share_name, schema_name, table_name)
             .await?;
+        if query.version != TableDataVersion::Snapshot(Version::Latest) {
+            ensure_history_shared(&table)?;
+        }
+        let cursor = QueryCursor::from_page(&query.page, &table)?;
+
+        let reader =
+            self.table_reader(table.format())
+                .ok_or(ServerError::UnsupportedTableFormat {
+                    format: table.format().to_owned(),
+                })?;
+        let mut refresh_token = None;
+        let (pinned_version, mut table_data) = match query.version {
+            TableDataVersion::Snapshot(version) => {
+                let (version, limit, predicate) = match query.refresh_token.as_deref() {
+                    Some(token) => {
+                        let token = RefreshToken::decode(
+                            token,
+                            &self.refresh_token_key,
+                            self.refresh_token_lifetime,
+                        )?;
+                        if token.table != full_table_name(&table) {
+                            return Err(ServerError::InvalidRefreshToken {
+                                reason: String::from(
+                                    "the refresh token was issued for another table",
+                                ),
+                            });
+                        }
+                        let predicate = token
+                            .predicate
+                            .as_deref()
+                            .map(Predicate::from_json)
+                            .transpose()
+                            .map_err(|e| ServerError::InvalidRefreshToken {
+                                reason: e.to_string(),
+                            })?;
+                        (Version::Number(token.version), token.limit, predicate)
+                    }
+                    None => (version, query.limit, query.predicate),
+                };
+
+                let table_version = match cursor.version {
+                    Some(pinned_version) => pinned_version,
+                    None => {
+                        reader
+                            .get_table_version(table.storage_path(), version)
+                            .await?
+                    }
+                };
+                ensure_version_shared(&table, table_version)?;
+                let predicate_json = predicate.as_ref().map(Predicate::to_json);
+                let table_data = reader
+                    .get_table_data_stream(table.storage_path(), table_version, limit, predicate)
+                    .await?;
+                if query.include_refresh_token || query.refresh_token.is_some() {
+                    let token = RefreshToken {
+                        table: full_table_name(&table),
+                        version: table_data.version,
+                        limit,
+                        predicate: predicate_json,
+                        issued_at: chrono::Utc::now().timestamp(),
+                    };
+                    refresh_token = Some(token.encode(&self.refresh_token_key));
+                }
+                (table_data.version, table_data)
+            }
+            TableDataVersion::Since { .. } if query.refresh_token.is_some() => {
+                return Err(ServerError::InvalidRefreshToken {
+                    reason: String::from(
+                        "the urls of a query with a starting version cannot be refreshed",
+                    ),
+                });
+            }
+            TableDataVersion::Since { start, end } => {
+                ensure_version_shared(&table, start)?;
+                let ending_version = match (cursor.version, end) {
+                    (Some(pinned_version), _) => pinned_version,
+                    (None, Some(end)) => end,
+                    (None, None) => {
+                        reader
+                            .get_table_version(table.storage_path(), Version::Latest)
+                            .await?
+                    }
+                };
+                let table_data = reader
+                    .get_table_data_since(table.storage_path(), start, ending_version)
+                    .await?;
+                (ending_version, table_data.into())
+            }
+        };
+        let response_capabilities = capabilities.negotiate(&table_data.protocol)?;
+        let (data, mut end_stream_action) =
+            cursor.page(table_data.data, &query.page, pinned_version);
+        table_data.data = data;
+        if let Some(refresh_token) = refresh_token {
+            let action = end_stream_action
+                .unwrap_or_else(|| future::ready(EndStreamAction::default()).boxed());
+            end_stream_action = Some(
+                action
+                    .map(|action| action.with_refresh_token(refresh_token))
+                    .boxed(),
+            );
+        }
 
-        let table_data = self
-            .table_reader(table.format())
-            .ok_or(ServerError::UnsupportedTableFormat {
-                format: table.format().to_owned(),
-            })?
-            .get_table_data(table.storage_path(), 0, None, None)
+        self.table_actions_response(
+            table.storage_path(),
+            table_data,
+            end_stream_action,
+            response_capabilities,
+        )
+        .await
+    }
+
+    /// Get the change data files of a range of table versions.
+    ///
+    /// When the client paginates, the response is restricted to a page of
+    /// change data files. The response format is negotiated with the
+    /// capabilities of the client.
+    pub async fn get_table_changes(
+        &self,
+        recipient: &RecipientId,
+        share_name: &str,
+        schema_name: &str,
+        table_name: &str,
+        query: TableChangesQuery,
+        capabilities: &Capabilities,
+    ) -> Result<TableActionsResponse, ServerError> {
+        self.authorize_share(recipient, share_name).await?;
+        let table = self
+            .table_manager()
+            .get_table(share_name, schema_name, table_name)
             .await?;
+        ensure_history_shared(&table)?;
+        let cursor = QueryCursor::from_page(&query.page, &table)?;
+
+        let reader =
+            self.table_reader(table.format())
+                .ok_or(ServerError::UnsupportedTableFormat {
+                    format: table.format().to_owned(),
+                })?;
+        if table.start_version().is_some() {
+            let starting_version = match query.range {
+                VersionRange::Version { start, .. } => start,
+                VersionRange::Timestamp { start, .. } => {
+                    reader
+                        .get_table_version(table.storage_path(), Version::Timestamp(start))
+                        .await?
+                }
+            };
+            ensure_version_shared(&table, starting_version)?;
+        }
+        let mut table_changes: UnsignedTableDataStream = reader
+            .get_table_changes(table.storage_path(), query.range)
+            .await?
+            .into();
+        // The range of versions is fixed by the query, so the token of a
+        // later page must have been issued for the same starting version.
+        if cursor.version.is_some_and(|v| v != table_changes.version) {
+            return Err(ServerError::InvalidPaginationToken {
+                reason: String::from("the page token was issued for another version range"),
+            });
+        }
+        let response_capabilities = capabilities.negotiate(&table_changes.protocol)?;
+        let (data, end_stream_action) =
+            cursor.page(table_changes.data, &query.page, table_changes.version);
+        table_changes.data = data;
+
+        self.table_actions_response(
+            table.storage_path(),
+            table_changes,
+            end_stream_action,
+            response_capabilities,
+        )
+        .await
+    }
+
+    /// Create the response to a table data or table changes query, in which
+    /// the data files are signed while they are written.
+    ///
+    /// The first data file is signed before the response is created, so a
+    /// signer that cannot sign the data files of the table fails the request
+    /// instead of the response body.
+    async fn table_actions_response(
+        &self,
+        storage_path: &str,
+        table_data: UnsignedTableDataStream,
+        end_stream_action: Option<BoxFuture<'static, EndStreamAction>>,
+        capabilities: ResponseCapabilities,
+    ) -> Result<TableActionsResponse, ServerError> {
+        let signer = self.url_signer_for_location(storage_path)?;
+        let mut data = sign_data_file_stream(
+            signer,
+            table_data.data.map_err(ServerError::from).boxed(),
+            self.signing_concurrency,
+        );
+        let first_data_file = data.try_next().await?;
+        let data = stream::iter(first_data_file.map(Ok)).chain(data).boxed();
+
+        Ok(TableActionsResponse::from_table_data_stream(
+            table_data.version,
+            table_data.protocol,
+            table_data.metadata,
+            data,
+            end_stream_action,
+            capabilities,
+        ))
+    }
+
+    /// Get the names of the shares granted to the recipient. Anonymous
+    /// recipients are not subject to grants, which is represented by `None`.
+    async fn share_grants(
+        &self,
+        recipient: &RecipientId,
+    ) -> Result<Option<Vec<String>>, ServerError> {
+        match recipient {
+            RecipientId::Anonymous => Ok(None),
+            RecipientId::Known(name) => {
+                let grants = self.shared_table_manager.list_share_grants(name).await?;
+                Ok(Some(grants))
+            }
+        }
+    }
 
-        let signer = self
-            .url_signer("S3")
-            .ok_or(ServerError::UnsupportedTableStorage {
-                storage: String::from("S3"),
-            })?;
+    /// Verify that the recipient is granted access to the share.
+    ///
+    /// Shares that are not granted to the recipient are reported as not found,
+    /// so the recipient cannot distinguish them from missing shares.
+    async fn authorize_share(
+        &self,
+        recipient: &RecipientId,
+        share_name: &str,
+    ) -> Result<(), ServerError> {
+        match self.share_grants(recipient).await? {
+            Some(grants) if !grants.iter().any(|grant| grant == share_name) => {
+                Err(ServerError::ShareNotFound {
+                    name: share_name.to_owned(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn full_table_name(table: &Table) -> String {
+    format!(
+        "{}.{}.{}",
+        table.share_name(),
+        table.schema_name(),
+        table.name()
+    )
+}
+
+/// Ensure that the history of the table is shared, which is required to
+/// query other versions than the latest version of the table.
+fn ensure_history_shared(table: &Table) -> Result<(), ServerError> {
+    if table.history_shared() {
+        return Ok(());
+    }
+    Err(ServerError::TableHistoryNotShared {
+        reason: format!(
+            "the history of table `{}` is not shared",
+            full_table_name(table)
+        ),
+    })
+}
+
+/// Ensure that the table version is not before the first shared version of
+/// the table.
+fn ensure_version_shared(table: &Table, version: u64) -> Result<(), ServerError> {
+    match table.start_version() {
+        Some(start_version) if version < start_version => Err(ServerError::TableHistoryNotShared {
+            reason: format!(
+                "table `{}` is only shared from version {}",
+                full_table_name(table),
+                start_version
+            ),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Position in the data files of a paginated table query.
+///
+/// The token of the next page holds the table version of the first page, so
+/// every page describes the same version of the table.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct QueryCursor {
+    table: String,
+    version: Option<u64>,
+    offset: u64,
+}
+
+impl QueryCursor {
+    /// Resume from the page token, which must have been issued for the same
+    /// table. Without a page token the query starts at the first data file.
+    fn from_page(page: &ListCursor, table: &Table) -> Result<Self, ServerError> {
+        let table_name = full_table_name(table);
+        let token = match page.page_token() {
+            Some(token) => token,
+            None => {
+                return Ok(Self {
+                    table: table_name,
+                    ..Default::default()
+                })
+            }
+        };
+
+        let invalid_token = || ServerError::InvalidPaginationToken {
+            reason: String::from("the provided `pageToken` is malformed"),
+        };
+        let decoded_token = general_purpose::URL_SAFE
+            .decode(token)
+            .map_err(|_| invalid_token())?;
+        let cursor = serde_json::from_slice::<Self>(&decoded_token).map_err(|_| invalid_token())?;
+        if cursor.table != table_name || cursor.version.is_none() {
+            return Err(invalid_token());
+        }
+        Ok(cursor)
+    }
+
+    /// Restrict the data files to the requested page.
+    ///
+    /// When the client paginates, the action that ends the response is
+    /// returned as well. Once the data files of the page have been consumed,
+    /// it holds the token of the next page if data files remain.
+    fn page<T: Send + 'static>(
+        &self,
+        data: BoxStream<'static, T>,
+        page: &ListCursor,
+        pinned_version: u64,
+    ) -> (
+        BoxStream<'static, T>,
+        Option<BoxFuture<'static, EndStreamAction>>,
+    ) {
+        if page.max_results().is_none() && !page.has_page_token() {
+            return (data, None);
+        }
+
+        let offset = usize::try_from(self.offset).unwrap_or(usize::MAX);
+        let data = data.skip(offset);
+        let max_files = match page.max_results() {
+            Some(max_files) => max_files as usize,
+            None => {
+                let end_stream_action = future::ready(EndStreamAction::new(None));
+                return (data.boxed(), Some(end_stream_action.boxed()));
+            }
+        };
+
+        // One data file beyond the page is read to find out whether a next
+        // page exists.
+        let has_next_page = Arc::new(AtomicBool::new(false));
+        let data = data.take(max_files + 1).enumerate().filter_map({
+            let has_next_page = has_next_page.clone();
+            move |(index, data_file)| {
+                if index == max_files {
+                    has_next_page.store(true, Ordering::Relaxed);
+                    future::ready(None)
+                } else {
+                    future::ready(Some(data_file))
+                }
+            }
+        });
+        let next_cursor = Self {
+            table: self.table.clone(),
+            version: Some(pinned_version),
+            offset: self.offset + max_files as u64,
+        };
+        let end_stream_action = async move {
+            let next_page_token = has_next_page
+                .load(Ordering::Relaxed)
+                .then(|| next_cursor.into_token());
+            EndStreamAction::new(next_page_token)
+        };
+        (data.boxed(), Some(end_stream_action.boxed()))
+    }
 
-        let signed_table_data = signer.sign_table_data(table_data).await;
-        Ok(signed_table_data.into())
+    fn into_token(self) -> String {
+        let value = serde_json::to_vec(&self).expect("query cursor can be serialized");
+        general_purpose::URL_SAFE.encode(value)
     }
 }
 
@@ -206,16 +772,69 @@ mod test {
     use crate::{
         manager::{MockShareReader, ShareIoError},
         protocol::{
-            action::{FileBuilder, MetadataBuilder, ProtocolBuilder},
+            action::{
+                AddBuilder, CdfBuilder, DeltaProtocol, FileBuilder, MetadataBuilder,
+                ProtocolBuilder,
+            },
             securable::{SchemaBuilder, ShareBuilder, TableBuilder},
             share::List,
-            table::{SignedDataFile, SignedTableData, TableMetadata, UnsignedTableData},
+            table::{
+                SignedDataFile, TableMetadata, UnsignedDataFile, UnsignedTableData, VersionRange,
+            },
         },
-        reader::MockTableReader,
-        signer::MockUrlSigner,
+        reader::{MockTableReader, TableReaderError},
+        signer::{MockUrlSigner, SignedUrl, SignerError},
     };
     use insta::assert_json_snapshot;
-    use mockall::predicate::eq;
+    use mockall::{predicate::eq, Sequence};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn select_url_signer_for_location() {
+        fn mock_url_signer(name: &'static str) -> Arc<dyn UrlSigner> {
+            let mut mock_url_signer = MockUrlSigner::new();
+            mock_url_signer
+                .expect_sign_url()
+                .returning(move |_| Ok(SignedUrl::unexpiring(name)));
+            Arc::new(mock_url_signer)
+        }
+
+        let mut state = SharingServerState::new(Arc::new(MockShareReader::new()));
+        state.add_url_signer("S3", mock_url_signer("s3"));
+        state.add_url_signer("azure", mock_url_signer("azure"));
+        state.add_url_signer("file", mock_url_signer("file"));
+        state.add_storage_alias("R2", "s3");
+
+        for (location, signer_name) in [
+            ("s3://bucket/table", "s3"),
+            ("s3a://bucket/table", "s3"),
+            ("S3N://bucket/table", "s3"),
+            ("r2://bucket/table", "s3"),
+            (
+                "abfss://container@account.dfs.core.windows.net/table",
+                "azure",
+            ),
+            (
+                "wasbs://container@account.blob.core.windows.net/table",
+                "azure",
+            ),
+            ("file:///tmp/table", "file"),
+            ("/tmp/table", "file"),
+        ] {
+            let signer = state.url_signer_for_location(location).unwrap();
+            assert_eq!(signer.sign_url(location).await.unwrap().url(), signer_name);
+        }
+
+        assert_eq!(
+            state
+                .url_signer_for_location("gs://bucket/table")
+                .err()
+                .unwrap(),
+            ServerError::UnsupportedTableStorage {
+                storage: String::from("gs")
+            }
+        );
+    }
 
     #[tokio::test]
     async fn list_shares() {
@@ -239,7 +858,10 @@ mod test {
             });
 
         let state = SharingServerState::new(Arc::new(mock_table_manager));
-        let response = state.list_shares(&ListCursor::default()).await.unwrap();
+        let response = state
+            .list_shares(&RecipientId::Anonymous, &ListCursor::default())
+            .await
+            .unwrap();
         assert_json_snapshot!(response);
     }
 
@@ -275,14 +897,17 @@ mod test {
             });
 
         let state = SharingServerState::new(Arc::new(mock_table_manager));
-        let response1 = state.list_shares(&ListCursor::default()).await.unwrap();
+        let response1 = state
+            .list_shares(&RecipientId::Anonymous, &ListCursor::default())
+            .await
+            .unwrap();
         assert_json_snapshot!(response1);
 
         let response2 = state
-            .list_shares(&ListCursor::new(
-                None,
-                Some("continuation_token".to_owned()),
-            ))
+            .list_shares(
+                &RecipientId::Anonymous,
+                &ListCursor::new(None, Some("continuation_token".to_owned())),
+            )
             .await
             .unwrap();
         assert_json_snapshot!(response2);
@@ -298,7 +923,10 @@ mod test {
 
         let state = SharingServerState::new(Arc::new(mock_table_manager));
         let response = state
-            .list_shares(&ListCursor::new(None, Some("invalid_token".to_owned())))
+            .list_shares(
+                &RecipientId::Anonymous,
+                &ListCursor::new(None, Some("invalid_token".to_owned())),
+            )
             .await;
         assert!(response.is_err());
         assert_eq!(
@@ -323,7 +951,10 @@ mod test {
             });
 
         let state = SharingServerState::new(Arc::new(mock_table_manager));
-        let response = state.get_share("vaccine_share").await.unwrap();
+        let response = state
+            .get_share(&RecipientId::Anonymous, "vaccine_share")
+            .await
+            .unwrap();
         assert_json_snapshot!(response);
     }
 
@@ -341,7 +972,9 @@ mod test {
             });
 
         let state = SharingServerState::new(Arc::new(mock_table_manager));
-        let response = state.get_share("vaccine_share").await;
+        let response = state
+            .get_share(&RecipientId::Anonymous, "vaccine_share")
+            .await;
         assert!(response.is_err());
         assert_eq!(
             response.unwrap_err(),
@@ -351,6 +984,132 @@ mod test {
         );
     }
 
+    #[tokio::test]
+    async fn list_shares_granted_to_recipient() {
+        let mut mock_table_manager = MockShareReader::new();
+        mock_table_manager
+            .expect_list_shares()
+            .once()
+            .returning(|_| {
+                let mut shares = List::new(vec![], Some("continuation_token".to_owned()));
+                shares.push(
+                    ShareBuilder::new("vaccine_share")
+                        .id("edacc4a7-6600-4fbb-85f3-a62a5ce6761f")
+                        .build(),
+                );
+                shares.push(
+                    ShareBuilder::new("sales_share")
+                        .id("3e979c79-6399-4dac-bcf8-54e268f48515")
+                        .build(),
+                );
+                Ok(shares)
+            });
+        mock_table_manager
+            .expect_list_share_grants()
+            .with(eq("acme"))
+            .once()
+            .returning(|_| Ok(vec!["sales_share".to_owned()]));
+
+        let state = SharingServerState::new(Arc::new(mock_table_manager));
+        let response = state
+            .list_shares(&RecipientId::known("acme"), &ListCursor::default())
+            .await
+            .unwrap();
+        assert_json_snapshot!(response);
+    }
+
+    #[tokio::test]
+    async fn list_shares_granted_to_recipient_fills_page() {
+        let mut mock_table_manager = MockShareReader::new();
+        let mut seq = Sequence::new();
+        for (page_token, names, next_page_token) in [
+            (None, vec!["a_share", "b_share"], Some("page_2")),
+            (Some("page_2"), vec!["c_share"], Some("page_3")),
+            (Some("page_3"), vec!["d_share"], Some("page_4")),
+        ] {
+            mock_table_manager
+                .expect_list_shares()
+                .withf(move |cursor| {
+                    cursor.page_token() == page_token
+                        && cursor.max_results() == Some(if page_token.is_none() { 2 } else { 1 })
+                })
+                .once()
+                .in_sequence(&mut seq)
+                .returning(move |_| {
+                    let shares = names.iter().map(|name| ShareBuilder::new(*name).build());
+                    Ok(List::new(
+                        shares.collect(),
+                        next_page_token.map(ToOwned::to_owned),
+                    ))
+                });
+        }
+        mock_table_manager
+            .expect_list_share_grants()
+            .with(eq("acme"))
+            .once()
+            .returning(|_| Ok(vec!["b_share".to_owned(), "d_share".to_owned()]));
+
+        let state = SharingServerState::new(Arc::new(mock_table_manager));
+        let response = state
+            .list_shares(&RecipientId::known("acme"), &ListCursor::new(Some(2), None))
+            .await
+            .unwrap();
+        let response = serde_json::to_value(response).unwrap();
+        assert_eq!(response["items"][0]["name"], "b_share");
+        assert_eq!(response["items"][1]["name"], "d_share");
+        assert_eq!(response["nextPageToken"], "page_4");
+    }
+
+    #[tokio::test]
+    async fn get_share_not_granted() {
+        let mut mock_table_manager = MockShareReader::new();
+        mock_table_manager
+            .expect_list_share_grants()
+            .with(eq("acme"))
+            .once()
+            .returning(|_| Ok(vec!["sales_share".to_owned()]));
+        mock_table_manager.expect_get_share().never();
+
+        let state = SharingServerState::new(Arc::new(mock_table_manager));
+        let response = state
+            .get_share(&RecipientId::known("acme"), "vaccine_share")
+            .await;
+        assert_eq!(
+            response.unwrap_err(),
+            ServerError::ShareNotFound {
+                name: "vaccine_share".to_owned()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn get_table_version_not_granted() {
+        let mut mock_table_manager = MockShareReader::new();
+        mock_table_manager
+            .expect_list_share_grants()
+            .with(eq("acme"))
+            .once()
+            .returning(|_| Ok(vec![]));
+        mock_table_manager.expect_get_table().never();
+
+        let state = SharingServerState::new(Arc::new(mock_table_manager));
+        let response = state
+            .get_table_version(
+                &RecipientId::known("acme"),
+                "vaccine_share",
+                "acme_vaccine_data",
+                "vaccine_patients",
+                Version::Latest,
+            )
+            .await;
+        assert_eq!(
+            response.unwrap_err(),
+            ServerError::ShareNotFound {
+                name: "vaccine_share".to_owned()
+            }
+        );
+    }
+
     #[tokio::test]
     async fn list_schemas() {
         let mut mock_table_manager = MockShareReader::new();
@@ -368,7 +1127,11 @@ mod test {
 
         let state = SharingServerState::new(Arc::new(mock_table_manager));
         let response = state
-            .list_schemas("vaccine_share", &ListCursor::default())
+            .list_schemas(
+                &RecipientId::Anonymous,
+                "vaccine_share",
+                &ListCursor::default(),
+            )
             .await
             .unwrap();
         assert_json_snapshot!(response);
@@ -410,7 +1173,11 @@ mod test {
 
         let state = SharingServerState::new(Arc::new(mock_table_manager));
         let response = state
-            .list_tables_in_share("vaccine_share", &ListCursor::default())
+            .list_tables_in_share(
+                &RecipientId::Anonymous,
+                "vaccine_share",
+                &ListCursor::default(),
+            )
             .await
             .unwrap();
         assert_json_snapshot!(response);
@@ -456,7 +1223,12 @@ mod test {
 
         let state = SharingServerState::new(Arc::new(mock_table_manager));
         let response = state
-            .list_tables_in_schema("vaccine_share", "acme_vaccine_data", &ListCursor::default())
+            .list_tables_in_schema(
+                &RecipientId::Anonymous,
+                "vaccine_share",
+                "acme_vaccine_data",
+                &ListCursor::default(),
+            )
             .await
             .unwrap();
         assert_json_snapshot!(response);
@@ -503,6 +1275,7 @@ mod test {
 
         let response = state
             .get_table_version(
+                &RecipientId::Anonymous,
                 "vaccine_share",
                 "acme_vaccine_data",
                 "vaccine_patients",
@@ -534,6 +1307,7 @@ mod test {
 
         let response = state
             .get_table_version(
+                &RecipientId::Anonymous,
                 "vaccine_share",
                 "acme_vaccine_data",
                 "missing_table",
@@ -550,71 +1324,164 @@ mod test {
     }
 
     #[tokio::test]
-    async fn get_table_version_internal_error() {
+    async fn get_table_version_history_not_shared() {
         let mut mock_table_manager = MockShareReader::new();
         mock_table_manager
             .expect_get_table()
-            .with(
-                eq("vaccine_share"),
-                eq("acme_vaccine_data"),
-                eq("vaccine_patients"),
-            )
             .once()
-            .return_const(Err(ShareIoError::Other {
-                reason: "something went wrong internally".to_owned(),
-            }));
+            .returning(|_, _, _| {
+                let share = ShareBuilder::new("vaccine_share").build();
+                let schema = SchemaBuilder::new(share, "acme_vaccine_data").build();
+                let table = TableBuilder::new(
+                    schema,
+                    "vaccine_patients",
+                    "s3://vaccine_share/acme_vaccine_data/vaccine_patients",
+                )
+                .history_shared(false)
+                .build();
+                Ok(table)
+            });
 
-        let state = SharingServerState::new(Arc::new(mock_table_manager));
+        let mut state = SharingServerState::new(Arc::new(mock_table_manager));
+        state.add_table_reader("DELTA", Arc::new(MockTableReader::new()));
 
         let response = state
             .get_table_version(
+                &RecipientId::Anonymous,
                 "vaccine_share",
                 "acme_vaccine_data",
                 "vaccine_patients",
-                Version::Latest,
+                Version::Number(3),
             )
             .await;
-        assert!(response.is_err());
         assert_eq!(
             response.unwrap_err(),
-            ServerError::ShareManagerError {
-                reason: String::from("something went wrong internally")
+            ServerError::TableHistoryNotShared {
+                reason: String::from(
+                    "the history of table `vaccine_share.acme_vaccine_data.vaccine_patients` is not shared"
+                )
             }
-        )
+        );
     }
 
     #[tokio::test]
-    async fn get_table_metadata() {
+    async fn get_table_version_before_start_version() {
         let mut mock_table_manager = MockShareReader::new();
         mock_table_manager
             .expect_get_table()
-            .with(
-                eq("vaccine_share"),
-                eq("acme_vaccine_data"),
-                eq("vaccine_patients"),
-            )
             .once()
             .returning(|_, _, _| {
-                let share = ShareBuilder::new("vaccine_share")
-                    .id("edacc4a7-6600-4fbb-85f3-a62a5ce6761f")
-                    .build();
+                let share = ShareBuilder::new("vaccine_share").build();
                 let schema = SchemaBuilder::new(share, "acme_vaccine_data").build();
-                Ok(TableBuilder::new(
+                let table = TableBuilder::new(
                     schema,
                     "vaccine_patients",
-                    "s3://vaccine_share/acme_vaccine_data/vaccine_patients".to_owned(),
+                    "s3://vaccine_share/acme_vaccine_data/vaccine_patients",
                 )
-                .id("c48f3e19-2c29-4ea3-b6f7-3899e53338fa")
-                .build())
+                .start_version(5)
+                .build();
+                Ok(table)
             });
 
         let mut mock_delta_reader = MockTableReader::new();
-
-        let table_metadata = MetadataBuilder::new("f8d5c169-3d01-4ca3-ad9e-7dc3355aedb2", "{\"type\":\"struct\",\"fields\":[{\"name\":\"eventTime\",\"type\":\"timestamp\",\"nullable\":true,\"metadata\":{}},{\"name\":\"date\",\"type\":\"date\",\"nullable\":true,\"metadata\":{}}]}").partition_columns(vec!["date".to_owned()]).build();
-
         mock_delta_reader
-            .expect_get_table_metadata()
-            .with(eq("s3://vaccine_share/acme_vaccine_data/vaccine_patients"))
+            .expect_get_table_version()
+            .with(
+                eq("s3://vaccine_share/acme_vaccine_data/vaccine_patients"),
+                eq(Version::Number(3)),
+            )
+            .once()
+            .return_const(Ok(3u64));
+
+        let mut state = SharingServerState::new(Arc::new(mock_table_manager));
+        state.add_table_reader("DELTA", Arc::new(mock_delta_reader));
+
+        let response = state
+            .get_table_version(
+                &RecipientId::Anonymous,
+                "vaccine_share",
+                "acme_vaccine_data",
+                "vaccine_patients",
+                Version::Number(3),
+            )
+            .await;
+        assert_eq!(
+            response.unwrap_err(),
+            ServerError::TableHistoryNotShared {
+                reason: String::from(
+                    "table `vaccine_share.acme_vaccine_data.vaccine_patients` is only shared from version 5"
+                )
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn get_table_version_internal_error() {
+        let mut mock_table_manager = MockShareReader::new();
+        mock_table_manager
+            .expect_get_table()
+            .with(
+                eq("vaccine_share"),
+                eq("acme_vaccine_data"),
+                eq("vaccine_patients"),
+            )
+            .once()
+            .return_const(Err(ShareIoError::Other {
+                reason: "something went wrong internally".to_owned(),
+            }));
+
+        let state = SharingServerState::new(Arc::new(mock_table_manager));
+
+        let response = state
+            .get_table_version(
+                &RecipientId::Anonymous,
+                "vaccine_share",
+                "acme_vaccine_data",
+                "vaccine_patients",
+                Version::Latest,
+            )
+            .await;
+        assert!(response.is_err());
+        assert_eq!(
+            response.unwrap_err(),
+            ServerError::ShareManagerError {
+                reason: String::from("something went wrong internally")
+            }
+        )
+    }
+
+    #[tokio::test]
+    async fn get_table_metadata() {
+        let mut mock_table_manager = MockShareReader::new();
+        mock_table_manager
+            .expect_get_table()
+            .with(
+                eq("vaccine_share"),
+                eq("acme_vaccine_data"),
+                eq("vaccine_patients"),
+            )
+            .once()
+            .returning(|_, _, _| {
+                let share = ShareBuilder::new("vaccine_share")
+                    .id("edacc4a7-6600-4fbb-85f3-a62a5ce6761f")
+                    .build();
+                let schema = SchemaBuilder::new(share, "acme_vaccine_data").build();
+                Ok(TableBuilder::new(
+                    schema,
+                    "vaccine_patients",
+                    "s3://vaccine_share/acme_vaccine_data/vaccine_patients".to_owned(),
+                )
+                .id("c48f3e19-2c29-4ea3-b6f7-3899e53338fa")
+                .build())
+            });
+
+        let mut mock_delta_reader = MockTableReader::new();
+
+        let table_metadata = MetadataBuilder::new("f8d5c169-3d01-4ca3-ad9e-7dc3355aedb2", "{\"type\":\"struct\",\"fields\":[{\"name\":\"eventTime\",\"type\":\"timestamp\",\"nullable\":true,\"metadata\":{}},{\"name\":\"date\",\"type\":\"date\",\"nullable\":true,\"metadata\":{}}]}").partition_columns(vec!["date".to_owned()]).build();
+
+        mock_delta_reader
+            .expect_get_table_metadata()
+            .with(eq("s3://vaccine_share/acme_vaccine_data/vaccine_patients"))
             .once()
             .return_const(Ok(TableMetadata {
                 version: 123u64,
@@ -626,12 +1493,67 @@ mod test {
         state.add_table_reader("DELTA", Arc::new(mock_delta_reader));
 
         let response = state
-            .get_table_metadata("vaccine_share", "acme_vaccine_data", "vaccine_patients")
+            .get_table_metadata(
+                &RecipientId::Anonymous,
+                "vaccine_share",
+                "acme_vaccine_data",
+                "vaccine_patients",
+                &Capabilities::default(),
+            )
+            .await
+            .unwrap()
+            .collect_lines()
             .await
             .unwrap();
         assert_json_snapshot!(response);
     }
 
+    /// Url signer that signs the data files as if they were stored in the
+    /// root of the test bucket.
+    fn test_bucket_url_signer() -> MockUrlSigner {
+        fn signed_url(url: &str) -> String {
+            let name = url.rsplit('/').next().unwrap_or_default();
+            format!(
+                "https://test-bucket.s3.eu-west-1.amazonaws.com/{}?signature=123",
+                name
+            )
+        }
+
+        let mut mock_url_signer = MockUrlSigner::new();
+        mock_url_signer
+            .expect_sign_data_file()
+            .times(2)
+            .returning(|data_file| {
+                Ok(match data_file {
+                    UnsignedDataFile::File(mut file) => {
+                        let url = signed_url(file.url());
+                        *file.url_mut() = url;
+                        SignedDataFile::File(file)
+                    }
+                    UnsignedDataFile::Add(mut add) => {
+                        add.url = signed_url(&add.url);
+                        SignedDataFile::Add(add)
+                    }
+                    UnsignedDataFile::Cdf(mut cdf) => {
+                        cdf.url = signed_url(&cdf.url);
+                        SignedDataFile::Cdf(cdf)
+                    }
+                    UnsignedDataFile::Remove(mut remove) => {
+                        remove.url = signed_url(&remove.url);
+                        SignedDataFile::Remove(remove)
+                    }
+                })
+            });
+        mock_url_signer
+    }
+
+    fn date_predicate() -> Predicate {
+        Predicate::from_json(
+            r#"{"op":"equal","children":[{"op":"column","name":"date","valueType":"date"},{"op":"literal","value":"2021-04-28","valueType":"date"}]}"#,
+        )
+        .unwrap()
+    }
+
     #[tokio::test]
     async fn get_table_data() {
         let mut mock_table_manager = MockShareReader::new();
@@ -661,75 +1583,711 @@ mod test {
 
         let mut mock_delta_reader = MockTableReader::new();
         mock_delta_reader
-            .expect_get_table_data()
+            .expect_get_table_version()
             .with(
                 eq("s3://vaccine_share/acme_vaccine_data/vaccine_patients"),
-                eq(0u64),
-                eq(None),
-                eq(None),
+                eq(Version::Number(123)),
+            )
+            .once()
+            .return_const(Ok(123u64));
+        mock_delta_reader
+            .expect_get_table_data_stream()
+            .with(
+                eq("s3://vaccine_share/acme_vaccine_data/vaccine_patients"),
+                eq(123u64),
+                eq(Some(10)),
+                eq(Some(date_predicate())),
+            )
+            .once()
+            .return_once(move |_, _, _, _| {
+                Ok(UnsignedTableData {
+                    version: 123u64,
+                    protocol: ProtocolBuilder::new().build(),
+                    metadata: table_metadata,
+                    data: vec![
+                        FileBuilder::new(
+                            "https://test-bucket.s3.eu-west-1.amazonaws.com/file1",
+                            "8b0086f2-7b27-4935-ac5a-8ed6215a6640",
+                        )
+                        .build()
+                        .into(),
+                        FileBuilder::new(
+                            "https://test-bucket.s3.eu-west-1.amazonaws.com/file2",
+                            "591723a8-6a27-4240-a90e-57426f4736d2",
+                        )
+                        .build()
+                        .into(),
+                    ],
+                }
+                .into())
+            });
+
+        let mock_url_signer = test_bucket_url_signer();
+
+        let mut state = SharingServerState::new(Arc::new(mock_table_manager));
+        state.add_table_reader("DELTA", Arc::new(mock_delta_reader));
+        state.add_url_signer("S3", Arc::new(mock_url_signer));
+
+        let response = state
+            .get_table_data(
+                &RecipientId::Anonymous,
+                "vaccine_share",
+                "acme_vaccine_data",
+                "vaccine_patients",
+                TableDataQuery {
+                    version: TableDataVersion::Snapshot(Version::Number(123)),
+                    limit: Some(10),
+                    predicate: Some(date_predicate()),
+                    ..Default::default()
+                },
+                &Capabilities::default(),
+            )
+            .await
+            .unwrap()
+            .collect_lines()
+            .await
+            .unwrap();
+        assert_json_snapshot!(response);
+    }
+
+    #[tokio::test]
+    async fn get_table_data_unsignable() {
+        let mut mock_table_manager = MockShareReader::new();
+        mock_table_manager
+            .expect_get_table()
+            .once()
+            .returning(|_, _, _| {
+                let share = ShareBuilder::new("vaccine_share").build();
+                let schema = SchemaBuilder::new(share, "acme_vaccine_data").build();
+                Ok(TableBuilder::new(
+                    schema,
+                    "vaccine_patients",
+                    "s3://vaccine_share/acme_vaccine_data/vaccine_patients",
+                )
+                .build())
+            });
+
+        let mut mock_delta_reader = MockTableReader::new();
+        mock_delta_reader
+            .expect_get_table_version()
+            .once()
+            .return_const(Ok(123u64));
+        mock_delta_reader
+            .expect_get_table_data_stream()
+            .once()
+            .return_once(move |_, _, _, _| {
+                Ok(UnsignedTableData {
+                    version: 123u64,
+                    protocol: ProtocolBuilder::new().build(),
+                    metadata: MetadataBuilder::new("f8d5c169-3d01-4ca3-ad9e-7dc3355aedb2", "{}")
+                        .build(),
+                    data: vec![
+                        FileBuilder::new("s3://", "8b0086f2-7b27-4935-ac5a-8ed6215a6640")
+                            .build()
+                            .into(),
+                    ],
+                }
+                .into())
+            });
+
+        let mut mock_url_signer = MockUrlSigner::new();
+        mock_url_signer
+            .expect_sign_data_file()
+            .once()
+            .return_once(|_| {
+                Err(SignerError::InvalidPath {
+                    path: String::from("s3://"),
+                })
+            });
+
+        let mut state = SharingServerState::new(Arc::new(mock_table_manager));
+        state.add_table_reader("DELTA", Arc::new(mock_delta_reader));
+        state.add_url_signer("S3", Arc::new(mock_url_signer));
+
+        let response = state
+            .get_table_data(
+                &RecipientId::Anonymous,
+                "vaccine_share",
+                "acme_vaccine_data",
+                "vaccine_patients",
+                TableDataQuery {
+                    version: TableDataVersion::Snapshot(Version::Number(123)),
+                    ..Default::default()
+                },
+                &Capabilities::default(),
+            )
+            .await;
+        assert_eq!(
+            response.err().unwrap(),
+            ServerError::UrlSignerError {
+                reason: String::from("the object store path `s3://` cannot be signed")
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn get_table_changes() {
+        let mut mock_table_manager = MockShareReader::new();
+        mock_table_manager
+            .expect_get_table()
+            .with(
+                eq("vaccine_share"),
+                eq("acme_vaccine_data"),
+                eq("vaccine_patients"),
+            )
+            .once()
+            .returning(|_, _, _| {
+                let share = ShareBuilder::new("vaccine_share")
+                    .id("edacc4a7-6600-4fbb-85f3-a62a5ce6761f")
+                    .build();
+                let schema = SchemaBuilder::new(share, "acme_vaccine_data").build();
+                Ok(TableBuilder::new(
+                    schema,
+                    "vaccine_patients",
+                    "s3://vaccine_share/acme_vaccine_data/vaccine_patients",
+                )
+                .id("c48f3e19-2c29-4ea3-b6f7-3899e53338fa")
+                .build())
+            });
+
+        let table_metadata = MetadataBuilder::new("f8d5c169-3d01-4ca3-ad9e-7dc3355aedb2", "{\"type\":\"struct\",\"fields\":[{\"name\":\"eventTime\",\"type\":\"timestamp\",\"nullable\":true,\"metadata\":{}},{\"name\":\"date\",\"type\":\"date\",\"nullable\":true,\"metadata\":{}}]}").partition_columns(vec!["date".to_owned()]).build();
+
+        let mut mock_delta_reader = MockTableReader::new();
+        mock_delta_reader
+            .expect_get_table_changes()
+            .with(
+                eq("s3://vaccine_share/acme_vaccine_data/vaccine_patients"),
+                eq(VersionRange::Version { start: 1, end: 2 }),
             )
             .once()
             .return_const(Ok(UnsignedTableData {
-                version: 123u64,
+                version: 1u64,
                 protocol: ProtocolBuilder::new().build(),
                 metadata: table_metadata,
                 data: vec![
-                    FileBuilder::new(
-                        "https://test-bucket.s3.eu-west-1.amazonaws.com/file1",
+                    AddBuilder::new(
+                        "s3://vaccine_share/acme_vaccine_data/vaccine_patients/file1",
                         "8b0086f2-7b27-4935-ac5a-8ed6215a6640",
                     )
+                    .size(573)
+                    .version(1)
+                    .timestamp("1652140000000")
                     .build()
                     .into(),
-                    FileBuilder::new(
-                        "https://test-bucket.s3.eu-west-1.amazonaws.com/file2",
+                    CdfBuilder::new(
+                        "s3://vaccine_share/acme_vaccine_data/vaccine_patients/_change_data/cdc1",
                         "591723a8-6a27-4240-a90e-57426f4736d2",
+                        1024,
+                        2,
+                        "1652150000000",
                     )
                     .build()
                     .into(),
                 ],
             }));
 
-        let table_metadata = MetadataBuilder::new("f8d5c169-3d01-4ca3-ad9e-7dc3355aedb2", "{\"type\":\"struct\",\"fields\":[{\"name\":\"eventTime\",\"type\":\"timestamp\",\"nullable\":true,\"metadata\":{}},{\"name\":\"date\",\"type\":\"date\",\"nullable\":true,\"metadata\":{}}]}").partition_columns(vec!["date".to_owned()]).build();
+        let mock_url_signer = test_bucket_url_signer();
 
-        let mut mock_url_signer = MockUrlSigner::new();
-        mock_url_signer
-            .expect_sign_table_data()
-            .times(1)
-            .return_const(SignedTableData {
-                version: 123u64,
+        let mut state = SharingServerState::new(Arc::new(mock_table_manager));
+        state.add_table_reader("DELTA", Arc::new(mock_delta_reader));
+        state.add_url_signer("S3", Arc::new(mock_url_signer));
+
+        let response = state
+            .get_table_changes(
+                &RecipientId::Anonymous,
+                "vaccine_share",
+                "acme_vaccine_data",
+                "vaccine_patients",
+                TableChangesQuery {
+                    range: VersionRange::Version { start: 1, end: 2 },
+                    page: ListCursor::default(),
+                },
+                &Capabilities::default(),
+            )
+            .await
+            .unwrap()
+            .collect_lines()
+            .await
+            .unwrap();
+        assert_json_snapshot!(response);
+    }
+
+    #[tokio::test]
+    async fn get_table_data_since() {
+        let mut mock_delta_reader = MockTableReader::new();
+        mock_delta_reader
+            .expect_get_table_version()
+            .with(
+                eq("s3://vaccine_share/acme_vaccine_data/vaccine_patients"),
+                eq(Version::Latest),
+            )
+            .once()
+            .return_const(Ok(3u64));
+        mock_delta_reader
+            .expect_get_table_data_since()
+            .with(
+                eq("s3://vaccine_share/acme_vaccine_data/vaccine_patients"),
+                eq(1),
+                eq(3),
+            )
+            .once()
+            .return_const(Ok(UnsignedTableData {
+                version: 1u64,
                 protocol: ProtocolBuilder::new().build(),
-                metadata: table_metadata,
-                data: vec![
-                    SignedDataFile::File(
-                        FileBuilder::new(
-                            "https://test-bucket.s3.eu-west-1.amazonaws.com/file1?signature=123",
-                            "8b0086f2-7b27-4935-ac5a-8ed6215a6640",
-                        )
+                metadata: MetadataBuilder::new("f8d5c169-3d01-4ca3-ad9e-7dc3355aedb2", "{}")
+                    .build(),
+                data: vec![AddBuilder::new(
+                    "s3://vaccine_share/acme_vaccine_data/vaccine_patients/file1",
+                    "8b0086f2-7b27-4935-ac5a-8ed6215a6640",
+                )
+                .size(573)
+                .version(2)
+                .timestamp("1652140000000")
+                .build()
+                .into()],
+            }));
+        let state = signing_state(mock_delta_reader);
+
+        let response = state
+            .get_table_data(
+                &RecipientId::Anonymous,
+                "vaccine_share",
+                "acme_vaccine_data",
+                "vaccine_patients",
+                TableDataQuery {
+                    version: TableDataVersion::Since {
+                        start: 1,
+                        end: None,
+                    },
+                    ..Default::default()
+                },
+                &Capabilities::default(),
+            )
+            .await
+            .unwrap()
+            .collect_lines()
+            .await
+            .unwrap();
+        let response = serde_json::to_value(&response).unwrap();
+        assert_eq!(response["version"], 1);
+        assert_eq!(
+            response["lines"][2],
+            json!({"add": {
+                "url": "https://vaccine_share/acme_vaccine_data/vaccine_patients/file1?signature=123",
+                "id": "8b0086f2-7b27-4935-ac5a-8ed6215a6640",
+                "partitionValues": {},
+                "size": 573,
+                "version": 2,
+                "timestamp": "1652140000000",
+            }})
+        );
+    }
+
+    #[tokio::test]
+    async fn get_table_data_paginated() {
+        let mut mock_delta_reader = MockTableReader::new();
+        mock_delta_reader
+            .expect_get_table_version()
+            .once()
+            .return_const(Ok(5u64));
+        mock_delta_reader
+            .expect_get_table_data_stream()
+            .withf(|_, version, _, _| *version == 5)
+            .times(2)
+            .returning(|_, version, _, _| {
+                let data = ["file1", "file2", "file3"]
+                    .into_iter()
+                    .map(|name| {
+                        FileBuilder::new(format!("s3://vaccine_share/{}", name), name)
+                            .build()
+                            .into()
+                    })
+                    .collect();
+                Ok(UnsignedTableData {
+                    version,
+                    protocol: ProtocolBuilder::new().build(),
+                    metadata: MetadataBuilder::new("f8d5c169-3d01-4ca3-ad9e-7dc3355aedb2", "{}")
                         .build(),
-                    ),
-                    SignedDataFile::File(
-                        FileBuilder::new(
-                            "https://test-bucket.s3.eu-west-1.amazonaws.com/file2?signature=123",
-                            "591723a8-6a27-4240-a90e-57426f4736d2",
-                        )
+                    data,
+                }
+                .into())
+            });
+        let state = signing_state(mock_delta_reader);
+
+        let query = TableDataQuery {
+            page: ListCursor::new(Some(2), None),
+            ..Default::default()
+        };
+        let response = state
+            .get_table_data(
+                &RecipientId::Anonymous,
+                "vaccine_share",
+                "acme_vaccine_data",
+                "vaccine_patients",
+                query,
+                &Capabilities::default(),
+            )
+            .await
+            .unwrap()
+            .collect_lines()
+            .await
+            .unwrap();
+        let response = serde_json::to_value(&response).unwrap();
+        let lines = response["lines"].as_array().unwrap();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[2]["file"]["id"], "file1");
+        assert_eq!(lines[3]["file"]["id"], "file2");
+        let next_page_token = lines[4]["endStreamAction"]["nextPageToken"]
+            .as_str()
+            .unwrap()
+            .to_owned();
+
+        // The second page is read from the pinned table version.
+        let query = TableDataQuery {
+            page: ListCursor::new(Some(2), Some(next_page_token.clone())),
+            ..Default::default()
+        };
+        let response = state
+            .get_table_data(
+                &RecipientId::Anonymous,
+                "vaccine_share",
+                "acme_vaccine_data",
+                "vaccine_patients",
+                query,
+                &Capabilities::default(),
+            )
+            .await
+            .unwrap()
+            .collect_lines()
+            .await
+            .unwrap();
+        let response = serde_json::to_value(&response).unwrap();
+        let lines = response["lines"].as_array().unwrap();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[2]["file"]["id"], "file3");
+        assert_eq!(lines[3], json!({"endStreamAction": {}}));
+
+        // The page token cannot be used for another table.
+        let query = TableDataQuery {
+            page: ListCursor::new(Some(2), Some(next_page_token)),
+            ..Default::default()
+        };
+        let response = state
+            .get_table_data(
+                &RecipientId::Anonymous,
+                "vaccine_share",
+                "acme_vaccine_data",
+                "vaccine_doses",
+                query,
+                &Capabilities::default(),
+            )
+            .await;
+        assert!(matches!(
+            response,
+            Err(ServerError::InvalidPaginationToken { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn get_table_data_refresh_token() {
+        let mut mock_delta_reader = MockTableReader::new();
+        mock_delta_reader
+            .expect_get_table_version()
+            .times(2)
+            .returning(|_, version| match version {
+                Version::Latest | Version::Number(5) => Ok(5),
+                _ => Err(TableReaderError::Other {
+                    reason: String::from("unexpected version"),
+                }),
+            });
+        mock_delta_reader
+            .expect_get_table_data_stream()
+            .withf(|_, version, limit, predicate| {
+                *version == 5 && *limit == Some(10) && *predicate == Some(date_predicate())
+            })
+            .times(2)
+            .returning(|_, version, _, _| {
+                Ok(UnsignedTableData {
+                    version,
+                    protocol: ProtocolBuilder::new().build(),
+                    metadata: MetadataBuilder::new("f8d5c169-3d01-4ca3-ad9e-7dc3355aedb2", "{}")
                         .build(),
-                    ),
-                ],
+                    data: vec![FileBuilder::new("s3://vaccine_share/file1", "file1")
+                        .build()
+                        .into()],
+                }
+                .into())
+            });
+        let state = signing_state(mock_delta_reader);
+
+        let query = TableDataQuery {
+            limit: Some(10),
+            predicate: Some(date_predicate()),
+            include_refresh_token: true,
+            ..Default::default()
+        };
+        let response = state
+            .get_table_data(
+                &RecipientId::Anonymous,
+                "vaccine_share",
+                "acme_vaccine_data",
+                "vaccine_patients",
+                query,
+                &Capabilities::default(),
+            )
+            .await
+            .unwrap()
+            .collect_lines()
+            .await
+            .unwrap();
+        let response = serde_json::to_value(&response).unwrap();
+        let lines = response["lines"].as_array().unwrap();
+        assert_eq!(lines.len(), 4);
+        let refresh_token = lines[3]["endStreamAction"]["refreshToken"]
+            .as_str()
+            .unwrap()
+            .to_owned();
+
+        // The refresh token replaces the version, limit and predicate.
+        let query = TableDataQuery {
+            refresh_token: Some(refresh_token.clone()),
+            ..Default::default()
+        };
+        let response = state
+            .get_table_data(
+                &RecipientId::Anonymous,
+                "vaccine_share",
+                "acme_vaccine_data",
+                "vaccine_patients",
+                query,
+                &Capabilities::default(),
+            )
+            .await
+            .unwrap()
+            .collect_lines()
+            .await
+            .unwrap();
+        let response = serde_json::to_value(&response).unwrap();
+        assert_eq!(response["version"], 5);
+        assert_eq!(
+            response["lines"][2]["file"]["url"],
+            "https://vaccine_share/file1?signature=123"
+        );
+
+        for (table_name, token) in [
+            ("vaccine_doses", refresh_token.clone()),
+            ("vaccine_patients", format!("{}x", refresh_token)),
+        ] {
+            let query = TableDataQuery {
+                refresh_token: Some(token),
+                ..Default::default()
+            };
+            let response = state
+                .get_table_data(
+                    &RecipientId::Anonymous,
+                    "vaccine_share",
+                    "acme_vaccine_data",
+                    table_name,
+                    query,
+                    &Capabilities::default(),
+                )
+                .await;
+            assert!(matches!(
+                response,
+                Err(ServerError::InvalidRefreshToken { .. })
+            ));
+        }
+    }
+
+    fn deletion_vectors_table_reader() -> MockTableReader {
+        let protocol = ProtocolBuilder::new()
+            .min_reader_version(3)
+            .delta_protocol(
+                DeltaProtocol::new(3, 7).with_reader_features(vec!["deletionVectors".to_owned()]),
+            )
+            .build();
+        let metadata = MetadataBuilder::new("f8d5c169-3d01-4ca3-ad9e-7dc3355aedb2", "{}")
+            .delta_metadata(json!({
+                "id": "f8d5c169-3d01-4ca3-ad9e-7dc3355aedb2",
+                "format": {"provider": "parquet", "options": {}},
+                "schemaString": "{}",
+                "partitionColumns": [],
+                "configuration": {"delta.enableDeletionVectors": "true"},
+            }))
+            .build();
+
+        let mut mock_delta_reader = MockTableReader::new();
+        mock_delta_reader
+            .expect_get_table_version()
+            .return_const(Ok(123u64));
+        mock_delta_reader
+            .expect_get_table_data_stream()
+            .return_once(move |_, _, _, _| Ok(UnsignedTableData {
+                version: 123u64,
+                protocol,
+                metadata,
+                data: vec![FileBuilder::new(
+                    "s3://vaccine_share/acme_vaccine_data/vaccine_patients/file1",
+                    "8b0086f2-7b27-4935-ac5a-8ed6215a6640",
+                )
+                .size(573)
+                .delta_action(json!({
+                    "path": "file1",
+                    "partitionValues": {},
+                    "size": 573,
+                    "modificationTime": 1652140000000i64,
+                    "dataChange": true,
+                    "deletionVector": {
+                        "storageType": "u",
+                        "pathOrInlineDv": "ab^-aqEH.-t@S}K{vb[*k^",
+                        "offset": 4,
+                        "sizeInBytes": 40,
+                        "cardinality": 6,
+                    },
+                }))
+                .deletion_vector(
+                    "f5a3b1a4be2b2e6e5ee7e7cb40ae7fa4",
+                    "s3://vaccine_share/acme_vaccine_data/vaccine_patients/ab/deletion_vector_d2c639aa-8816-431a-aaf6-d3fe2512ff61.bin",
+                )
+                .build()
+                .into()],
+            }.into()));
+        mock_delta_reader
+    }
+
+    fn signing_state(reader: MockTableReader) -> SharingServerState {
+        let mut mock_table_manager = MockShareReader::new();
+        mock_table_manager
+            .expect_get_table()
+            .returning(|_, _, table_name| {
+                let share = ShareBuilder::new("vaccine_share").build();
+                let schema = SchemaBuilder::new(share, "acme_vaccine_data").build();
+                Ok(TableBuilder::new(
+                    schema,
+                    table_name,
+                    "s3://vaccine_share/acme_vaccine_data/vaccine_patients",
+                )
+                .build())
             });
 
         let mut state = SharingServerState::new(Arc::new(mock_table_manager));
-        state.add_table_reader("DELTA", Arc::new(mock_delta_reader));
-        state.add_url_signer("S3", Arc::new(mock_url_signer));
+        state.add_table_reader("DELTA", Arc::new(reader));
+        state.add_url_signer("S3", Arc::new(HttpsUrlSigner));
+        state
+    }
+
+    struct HttpsUrlSigner;
+
+    #[async_trait::async_trait]
+    impl UrlSigner for HttpsUrlSigner {
+        async fn sign_url(&self, path: &str) -> Result<SignedUrl, SignerError> {
+            let url = format!("{}?signature=123", path.replace("s3://", "https://"));
+            Ok(SignedUrl::unexpiring(url))
+        }
+    }
+
+    #[tokio::test]
+    async fn get_table_data_delta_format() {
+        let state = signing_state(deletion_vectors_table_reader());
+        let capabilities =
+            Capabilities::from_header("responseformat=delta;readerfeatures=deletionvectors");
 
         let response = state
             .get_table_data(
+                &RecipientId::Anonymous,
                 "vaccine_share",
                 "acme_vaccine_data",
                 "vaccine_patients",
-                Version::Latest,
+                TableDataQuery::default(),
+                &capabilities,
             )
             .await
+            .unwrap()
+            .collect_lines()
+            .await
             .unwrap();
-        assert_json_snapshot!(response);
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            json!({
+                "version": 123,
+                "lines": [
+                    {"protocol": {"deltaProtocol": {
+                        "minReaderVersion": 3,
+                        "minWriterVersion": 7,
+                        "readerFeatures": ["deletionVectors"],
+                    }}},
+                    {"metaData": {"deltaMetadata": {
+                        "id": "f8d5c169-3d01-4ca3-ad9e-7dc3355aedb2",
+                        "format": {"provider": "parquet", "options": {}},
+                        "schemaString": "{}",
+                        "partitionColumns": [],
+                        "configuration": {"delta.enableDeletionVectors": "true"},
+                    }}},
+                    {"file": {
+                        "id": "8b0086f2-7b27-4935-ac5a-8ed6215a6640",
+                        "deletionVectorFileId": "f5a3b1a4be2b2e6e5ee7e7cb40ae7fa4",
+                        "deltaSingleAction": {"add": {
+                            "path": "https://vaccine_share/acme_vaccine_data/vaccine_patients/file1?signature=123",
+                            "partitionValues": {},
+                            "size": 573,
+                            "modificationTime": 1652140000000i64,
+                            "dataChange": true,
+                            "deletionVector": {
+                                "storageType": "p",
+                                "pathOrInlineDv": "https://vaccine_share/acme_vaccine_data/vaccine_patients/ab/deletion_vector_d2c639aa-8816-431a-aaf6-d3fe2512ff61.bin?signature=123",
+                                "offset": 4,
+                                "sizeInBytes": 40,
+                                "cardinality": 6,
+                            },
+                        }},
+                    }},
+                ],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn get_table_data_deletion_vectors_in_parquet_format() {
+        let state = signing_state(deletion_vectors_table_reader());
+
+        let response = state
+            .get_table_data(
+                &RecipientId::Anonymous,
+                "vaccine_share",
+                "acme_vaccine_data",
+                "vaccine_patients",
+                TableDataQuery::default(),
+                &Capabilities::default(),
+            )
+            .await;
+        assert_eq!(
+            response.err().unwrap(),
+            ServerError::UnsupportedClientCapabilities {
+                reason: String::from("the table uses reader feature `deletionVectors` and can only be shared in response format `delta`")
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn get_table_data_unsupported_reader_features() {
+        let state = signing_state(deletion_vectors_table_reader());
+        let capabilities = Capabilities::from_header("responseformat=delta");
+
+        let response = state
+            .get_table_data(
+                &RecipientId::Anonymous,
+                "vaccine_share",
+                "acme_vaccine_data",
+                "vaccine_patients",
+                TableDataQuery::default(),
+                &capabilities,
+            )
+            .await;
+        assert_eq!(
+            response.err().unwrap(),
+            ServerError::UnsupportedClientCapabilities {
+                reason: String::from("the table requires reader features that are not supported by the client: `deletionVectors`")
+            }
+        );
     }
 }
@@ -18,6 +18,26 @@ async fn list_shares_success() {
     assert_snapshot!(response.text().await);
 }
 
+#[tokio::test]
+async fn list_shares_unauthenticated() {
+    let test_client = TestClient::new().await;
+    let response = test_client.get("/shares").send().await;
+
+    response.assert_status_unauthorized();
+    response.assert_header_content_type_json();
+    assert_snapshot!(response.text().await);
+
+    let response = test_client
+        .get("/shares")
+        .header(AUTHORIZATION, "Bearer bar_token")
+        .send()
+        .await;
+
+    response.assert_status_unauthorized();
+    response.assert_header_content_type_json();
+    assert_snapshot!(response.text().await);
+}
+
 #[tokio::test]
 async fn list_shares_pagination() {
     let test_client = TestClient::new().await;
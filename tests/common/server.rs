@@ -7,6 +7,7 @@ use axum::http::{
 };
 use axum::Server;
 use bytes::Bytes;
+use delta_sharing_server::auth::static_token::StaticTokenAuthenticator;
 use delta_sharing_server::manager::dynamo::DynamoShareReader;
 use delta_sharing_server::reader::delta::DeltaTableReader;
 use delta_sharing_server::router::build_sharing_server_router;
@@ -34,7 +35,11 @@ impl TestClient {
         ));
         let mut state = SharingServerState::new(table_manager);
 
-        state.add_table_reader("DELTA", Arc::new(DeltaTableReader));
+        let mut authenticator = StaticTokenAuthenticator::new();
+        authenticator.add_token("foo_token", "foo");
+        state.set_authenticator(Arc::new(authenticator));
+
+        state.add_table_reader("DELTA", Arc::new(DeltaTableReader::new()));
         let s3_signer = S3UrlSigner::new(aws_sdk_s3::Client::new(&config));
         state.add_url_signer("s3", Arc::new(s3_signer));
 
@@ -147,6 +152,10 @@ impl TestResponse {
         assert_eq!(self.status(), StatusCode::BAD_REQUEST);
     }
 
+    pub fn assert_status_unauthorized(&self) {
+        assert_eq!(self.status(), StatusCode::UNAUTHORIZED);
+    }
+
     pub fn assert_status_not_found(&self) {
         assert_eq!(self.status(), StatusCode::NOT_FOUND);
     }
@@ -267,6 +267,9 @@ impl Manager {
                 let share2 = pg.insert_share("share_2").await.unwrap();
                 let _share3 = pg.insert_share("share_3").await.unwrap();
 
+                pg.insert_share_grant("recipient_1", &share1).await.unwrap();
+                pg.insert_share_grant("recipient_1", &share2).await.unwrap();
+
                 let schema_11 = pg.insert_schema(&share1, "schema_1").await.unwrap();
                 let schema_12 = pg.insert_schema(&share1, "schema_2").await.unwrap();
                 let _schema_21 = pg.insert_schema(&share2, "schema_1").await.unwrap();
@@ -301,6 +304,15 @@ impl Manager {
                 let share2 = mysql.insert_share("share_2").await.unwrap();
                 let _share3 = mysql.insert_share("share_3").await.unwrap();
 
+                mysql
+                    .insert_share_grant("recipient_1", &share1)
+                    .await
+                    .unwrap();
+                mysql
+                    .insert_share_grant("recipient_1", &share2)
+                    .await
+                    .unwrap();
+
                 let schema_11 = mysql.insert_schema(&share1, "schema_1").await.unwrap();
                 let schema_12 = mysql.insert_schema(&share1, "schema_2").await.unwrap();
                 let _schema_21 = mysql.insert_schema(&share2, "schema_1").await.unwrap();
@@ -371,6 +383,13 @@ impl Manager {
                     ddb.put_share(share).await.unwrap();
                 }
 
+                // Grant shares to recipients
+                for share_name in ["share_1", "share_2"] {
+                    ddb.put_share_grant("recipient_1", share_name)
+                        .await
+                        .unwrap();
+                }
+
                 // Add schemas to table manager
                 let schemas = [("1", "1"), ("1", "2"), ("2", "1")]
                     .into_iter()
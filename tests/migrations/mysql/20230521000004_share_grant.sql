@@ -0,0 +1,5 @@
+CREATE TABLE share_grant (
+    recipient_name VARCHAR(255) NOT NULL,
+    share_id INT NOT NULL REFERENCES share(id),
+    PRIMARY KEY (recipient_name, share_id)
+);
\ No newline at end of file
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS share_grant (
+    recipient_name VARCHAR NOT NULL,
+    share_id UUID NOT NULL REFERENCES share(id),
+    PRIMARY KEY (recipient_name, share_id)
+);
\ No newline at end of file
@@ -17,6 +17,7 @@ async fn postgres() {
     test_list_tables_in_share(manager).await;
     test_list_tables_in_schema(manager).await;
     test_get_table(manager).await;
+    test_list_share_grants(manager).await;
 
     ctx.teardown().await;
 }
@@ -32,6 +33,7 @@ async fn mysql() {
     test_list_tables_in_share(manager).await;
     test_list_tables_in_schema(manager).await;
     test_get_table(manager).await;
+    test_list_share_grants(manager).await;
 
     ctx.teardown().await;
 }
@@ -47,6 +49,7 @@ async fn dynamodb() {
     test_list_tables_in_share(manager).await;
     test_list_tables_in_schema(manager).await;
     // test_get_table(manager).await;
+    test_list_share_grants(manager).await;
 
     ctx.teardown().await;
 }
@@ -288,3 +291,14 @@ async fn test_get_table<M: ShareReader>(manager: &M) {
         Err(ShareIoError::TableNotFound { .. })
     ));
 }
+
+async fn test_list_share_grants<M: ShareReader>(manager: &M) {
+    // it should list the names of all shares granted to the recipient
+    let mut grants = manager.list_share_grants("recipient_1").await.unwrap();
+    grants.sort();
+    assert_eq!(grants, vec!["share_1", "share_2"]);
+
+    // it should return no grants for an unknown recipient
+    let grants = manager.list_share_grants("absent_recipient").await.unwrap();
+    assert!(grants.is_empty());
+}